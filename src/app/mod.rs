@@ -1,64 +1,195 @@
+pub mod palette;
+pub mod pending;
 pub mod state;
+pub mod tree;
 
-use self::state::{DiffState, RunningState};
+use self::{
+    palette::{Palette, PaletteItem},
+    pending::{KeyCommand, PendingKeys},
+    state::{DiffState, Focus, RunningState},
+    tree::{FileTree, TreeEntryKind},
+};
 use crate::{
-    services::{config::AppConfig, git::Diff, logger::Logs},
+    diff::Diff,
+    services::{
+        config::AppConfig,
+        conflict::{ConflictFile, Resolution},
+        image::ImageDiff,
+        logger::{ConsoleRecords, Logs},
+    },
     update::{keys::Key, message::Message},
 };
 use anyhow::Result;
 use crossterm::event::{self, Event, KeyEventKind};
 use ratatui::widgets::ListState;
-use std::{cell::RefCell, cmp, time::Duration};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tracing::Level;
 
 #[derive(Debug)]
 pub struct App {
     running_state: RunningState,
     // TODO: Model could do with a colours / styling section that can load a config for theming
     config: AppConfig,
-    diff: Diff,
+    diffs: Vec<Diff>,
+    /// Index into `diffs` of the file being shown
+    selected_file: usize,
+    /// Set when viewing a file with merge conflicts instead of a regular diff
+    conflict: Option<ConflictFile>,
+    /// Decoded old and new versions of image files, by path
+    images: HashMap<String, ImageDiff>,
+    /// Whether image files show their preview rather than their diff
+    show_image_preview: bool,
+    /// Whether tabs, carriage returns and trailing spaces are drawn as symbols
+    show_whitespace: bool,
+    /// Changed files by directory, shown in the sidebar
+    tree: FileTree,
+    show_tree: bool,
+    focus: Focus,
+    /// Keys typed towards a multi-key binding or count
+    pending_keys: PendingKeys,
+    /// Count typed before the binding of the message being handled
+    count: Option<usize>,
+    /// The command palette, while it's open
+    palette: Option<Palette>,
+    show_help: bool,
+    /// First line of the help overlay on screen
+    help_scroll: usize,
     diff_state: DiffState,
     logs: Logs,
+    /// Least severe level shown in the console, every level when `None`
+    console_level: Option<Level>,
+    /// Whether the console keeps the newest record selected as records arrive
+    follow_console: bool,
     console_state: RefCell<ListState>,
     /// Default value is 250 millis
     tick_rate: Duration,
 }
 
+/// Edits the palette's query or moves its selection. `None` when the key closes the palette,
+/// otherwise the message to send, if any.
+fn handle_palette_key(palette: &mut Palette, key: Key) -> Option<Option<Message>> {
+    match key {
+        Key::Esc | Key::Ctrl('c') => return None,
+        Key::Enter => return Some(Some(Message::ConfirmPalette)),
+        Key::Down | Key::Tab | Key::Ctrl('n') => palette.select_next(),
+        Key::Up | Key::Ctrl('p') => palette.select_previous(),
+        Key::Backspace => palette.pop(),
+        Key::Char(c) | Key::Shift(c) => palette.push(c),
+        _ => {}
+    }
+    Some(None)
+}
+
 impl App {
-    pub fn new(logs: Logs) -> Self {
+    pub fn new(logs: Logs, config: AppConfig) -> Self {
         let mut new = Self {
             running_state: Default::default(),
-            // TODO: This should be handled with a default config probably
-            config: AppConfig::new().expect("A config"),
-            diff: Default::default(),
+            config,
+            diffs: Default::default(),
+            selected_file: 0,
+            conflict: None,
+            images: Default::default(),
+            show_image_preview: true,
+            show_whitespace: false,
+            tree: Default::default(),
+            show_tree: false,
+            focus: Focus::Diff,
+            pending_keys: Default::default(),
+            count: None,
+            palette: None,
+            show_help: false,
+            help_scroll: 0,
             diff_state: Default::default(),
             logs,
+            console_level: None,
+            follow_console: true,
             console_state: Default::default(),
             tick_rate: Duration::from_millis(250),
         };
 
         new.handle_console();
 
-        return new;
+        new
     }
 
     pub fn console_state(&self) -> &RefCell<ListState> {
         &self.console_state
     }
 
+    /// Keeps the newest record selected, unless the console was scrolled back
     pub fn handle_console(&mut self) {
-        let console_length = self.console().len();
-        let console_state_index = cmp::max(1, console_length) - 1;
+        if self.follow_console {
+            let last = self.console().len().saturating_sub(1);
+            self.console_state.borrow_mut().select(Some(last));
+        }
+    }
+
+    /// Moves the console selection by `rows`, following new records again from the last one
+    fn scroll_console(&mut self, rows: isize) {
+        let last = self.console().len().saturating_sub(1);
+        let selected = self.console_state.borrow().selected().unwrap_or(last);
+        let selected = selected.saturating_add_signed(rows).min(last);
 
-        self.console_state
-            .borrow_mut()
-            .select(Some(console_state_index));
+        self.console_state.borrow_mut().select(Some(selected));
+        self.follow_console = selected == last;
     }
 
     pub fn update(&mut self, msg: Message) -> Option<Message> {
-        tracing::info!("{}", msg);
+        let count = self.count.take();
+        match count {
+            Some(count) => tracing::info!("{} ({}×)", msg, count),
+            None => tracing::info!("{}", msg),
+        }
         self.handle_console();
 
+        match (msg, count) {
+            // A count gives the row to jump to, as in vim's `10gg` or `10G`
+            (Message::FirstRow | Message::LastRow, Some(row)) if self.diff_focused() => {
+                self.go_to_row(row);
+                None
+            }
+            // Stops at the first follow-up message, so none are dropped
+            (msg, Some(count)) if msg.repeatable() => (0..count).find_map(|_| self.apply(msg)),
+            (msg, count) => {
+                if count.is_some() {
+                    tracing::debug!("Ignoring the count for {}", msg);
+                }
+                self.apply(msg)
+            }
+        }
+    }
+
+    /// Handles a message, returning any message it leads to
+    fn apply(&mut self, msg: Message) -> Option<Message> {
         match msg {
+            Message::PrevRow if self.tree_focused() => {
+                self.tree.select_previous();
+            }
+            Message::NextRow if self.tree_focused() => {
+                self.tree.select_next();
+            }
+            Message::LastRow if self.tree_focused() => {
+                self.tree.select_last();
+            }
+            Message::FirstRow if self.tree_focused() => {
+                self.tree.select_first();
+            }
+            Message::PrevRow if self.focus == Focus::Console => {
+                self.scroll_console(-1);
+            }
+            Message::NextRow if self.focus == Focus::Console => {
+                self.scroll_console(1);
+            }
+            Message::LastRow if self.focus == Focus::Console => {
+                self.scroll_console(isize::MAX);
+            }
+            Message::FirstRow if self.focus == Focus::Console => {
+                self.scroll_console(isize::MIN);
+            }
             Message::PrevRow => {
                 self.previous_row();
             }
@@ -75,12 +206,89 @@ impl App {
                 // Handle some exit stuff
                 self.quit();
             }
+            Message::NextFile => {
+                self.next_file();
+            }
+            Message::PrevFile => {
+                self.previous_file();
+            }
+            Message::NextConflict => {
+                self.next_conflict();
+            }
+            Message::PrevConflict => {
+                self.previous_conflict();
+            }
+            Message::PickOurs => {
+                self.resolve_conflict(Resolution::Ours);
+            }
+            Message::PickTheirs => {
+                self.resolve_conflict(Resolution::Theirs);
+            }
+            Message::PickBoth => {
+                self.resolve_conflict(Resolution::Both);
+            }
+            Message::WriteResolved => {
+                self.write_resolved();
+            }
+            Message::ToggleImagePreview => {
+                self.show_image_preview = !self.show_image_preview;
+            }
+            Message::ToggleWhitespace => {
+                self.show_whitespace = !self.show_whitespace;
+            }
+            Message::GoToMoved => {
+                self.go_to_moved_counterpart();
+            }
+            Message::ToggleFileTree => {
+                self.show_tree = !self.show_tree && !self.tree.is_empty();
+                if !self.show_tree {
+                    self.focus = Focus::Diff;
+                }
+            }
+            Message::SwitchFocus => {
+                self.focus = match self.focus {
+                    Focus::Diff if self.show_tree => Focus::Tree,
+                    Focus::Diff | Focus::Tree => Focus::Console,
+                    Focus::Console => Focus::Diff,
+                };
+            }
+            Message::OpenFile => {
+                self.open_tree_entry();
+            }
+            Message::OpenPalette => {
+                self.palette = Some(Palette::new(&self.diffs));
+            }
+            Message::ConfirmPalette => {
+                return self.confirm_palette();
+            }
+            Message::CycleLogLevel => {
+                self.console_level = match self.console_level {
+                    None => Some(Level::DEBUG),
+                    Some(Level::DEBUG) => Some(Level::INFO),
+                    Some(Level::INFO) => Some(Level::WARN),
+                    Some(Level::WARN) => Some(Level::ERROR),
+                    Some(_) => None,
+                };
+                self.follow_console = true;
+                self.handle_console();
+            }
+            Message::ToggleHelp => {
+                self.show_help = !self.show_help;
+                self.help_scroll = 0;
+            }
         }
-
         None
     }
 
-    pub fn handle_event(&self) -> Result<Option<Message>> {
+    pub fn handle_event(&mut self) -> Result<Option<Message>> {
+        // A binding that is also the start of a longer one fires once no more keys follow
+        let expired = self
+            .pending_keys
+            .expire(self.config.keymap(), Instant::now());
+        if let Some(command) = expired {
+            return Ok(Some(self.run_command(command)));
+        }
+
         if event::poll(self.tick_rate)? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
@@ -92,14 +300,91 @@ impl App {
         Ok(None)
     }
 
-    fn handle_key(&self, key: Key) -> Option<Message> {
-        let key_string = key.to_string();
-        let key = self.config.keymap().get(&key_string);
-        key.cloned()
+    fn handle_key(&mut self, key: Key) -> Option<Message> {
+        if let Some(palette) = self.palette.as_mut() {
+            return handle_palette_key(palette, key).unwrap_or_else(|| {
+                self.palette = None;
+                None
+            });
+        }
+        if self.show_help {
+            return self.handle_help_key(key);
+        }
+
+        let command = self
+            .pending_keys
+            .push(key, self.config.keymap(), Instant::now())?;
+        Some(self.run_command(command))
+    }
+
+    /// Hands the count over to `update` along with the message
+    fn run_command(&mut self, command: KeyCommand) -> Message {
+        self.count = command.count;
+        command.message
+    }
+
+    /// Closes the palette and runs its selected action, or shows its selected file
+    fn confirm_palette(&mut self) -> Option<Message> {
+        match self.palette.take()?.selected_item()? {
+            PaletteItem::Action(message) => Some(*message),
+            PaletteItem::File { index, .. } => {
+                let index = *index;
+                self.select_file(index);
+                None
+            }
+        }
+    }
+
+    /// Scrolls the help overlay, or closes it on `esc`, `q` or `?`
+    fn handle_help_key(&mut self, key: Key) -> Option<Message> {
+        match key {
+            Key::Esc | Key::Char('q') | Key::Char('?') => return Some(Message::ToggleHelp),
+            Key::Down | Key::Char('j') => self.help_scroll += 1,
+            Key::Up | Key::Char('k') => self.help_scroll = self.help_scroll.saturating_sub(1),
+            Key::PageDown | Key::Char(' ') => self.help_scroll += 10,
+            Key::PageUp => self.help_scroll = self.help_scroll.saturating_sub(10),
+            Key::Home | Key::Char('g') => self.help_scroll = 0,
+            _ => {}
+        }
+        None
+    }
+
+    pub fn show_help(&self) -> bool {
+        self.show_help
+    }
+
+    pub fn help_scroll(&self) -> usize {
+        self.help_scroll
+    }
+
+    /// Keeps the help overlay from scrolling past its last line
+    pub fn clamp_help_scroll(&mut self, max: usize) {
+        self.help_scroll = self.help_scroll.min(max);
+    }
+
+    pub fn palette(&self) -> Option<&Palette> {
+        self.palette.as_ref()
+    }
+
+    /// Whether something is drawn over the body, hiding image previews
+    pub fn overlay_open(&self) -> bool {
+        self.palette.is_some() || self.show_help
+    }
+
+    /// The count and keys typed towards a binding, empty when there are none
+    pub fn pending_keys(&self) -> &PendingKeys {
+        &self.pending_keys
+    }
+
+    /// The log records shown in the console, those at or above `console_level`, holding the logs'
+    /// lock until dropped
+    pub fn console(&self) -> ConsoleRecords<'_> {
+        ConsoleRecords::new(&self.logs, self.console_level)
     }
 
-    pub fn console(&self) -> Vec<String> {
-        return self.logs.lock().unwrap().clone();
+    /// Least severe level shown in the console, every level when `None`
+    pub fn console_level(&self) -> Option<Level> {
+        self.console_level
     }
 
     pub fn config(&self) -> &AppConfig {
@@ -110,15 +395,67 @@ impl App {
         &self.diff_state
     }
 
+    /// The diff for the selected file
     pub fn diff(&self) -> Option<&Diff> {
-        if self.diff.old_diff().len() != 0 && self.diff.current_diff().len() != 0 {
-            return Some(&self.diff);
-        }
-        None
+        self.diffs.get(self.selected_file)
+    }
+
+    pub fn diffs(&self) -> &[Diff] {
+        &self.diffs
+    }
+
+    pub fn selected_file(&self) -> usize {
+        self.selected_file
     }
 
     pub fn set_diff(&mut self, diff_string: &str) {
-        self.diff = Diff::parse_diff(diff_string)
+        self.set_diffs(vec![Diff::parse_diff(diff_string)])
+    }
+
+    /// Sets the files to view, files without any lines to show are dropped
+    pub fn set_diffs(&mut self, diffs: Vec<Diff>) {
+        self.diffs = diffs.into_iter().filter(|diff| !diff.is_empty()).collect();
+        self.tree = FileTree::new(&self.diffs);
+        // A single file has no need for orientation
+        self.show_tree = self.diffs.len() > 1;
+        self.select_file(0);
+    }
+
+    pub fn conflict(&self) -> Option<&ConflictFile> {
+        self.conflict.as_ref()
+    }
+
+    pub fn set_conflict(&mut self, conflict: ConflictFile) {
+        self.conflict = Some(conflict)
+    }
+
+    pub fn set_images(&mut self, images: HashMap<String, ImageDiff>) {
+        self.images = images
+    }
+
+    /// The preview to show in place of `diff`, when it's an image file and previews are on
+    pub fn image_preview(&self, diff: &Diff) -> Option<&ImageDiff> {
+        if !self.show_image_preview {
+            return None;
+        }
+        self.images.get(diff.path()?)
+    }
+
+    pub fn show_whitespace(&self) -> bool {
+        self.show_whitespace
+    }
+
+    pub fn tree(&self) -> &FileTree {
+        &self.tree
+    }
+
+    /// Whether the file tree sidebar is drawn
+    pub fn show_tree(&self) -> bool {
+        self.show_tree
+    }
+
+    pub fn focus(&self) -> Focus {
+        self.focus
     }
 
     pub fn running_state(&self) -> &RunningState {
@@ -129,81 +466,217 @@ impl App {
         self.running_state = RunningState::Done
     }
 
+    /// Number of rows in the panes currently on screen
+    fn row_count(&self) -> usize {
+        match (&self.conflict, self.diff()) {
+            (Some(conflict), _) => conflict.rows().len(),
+            (None, Some(diff)) => diff.longest_diff_len() + 1,
+            (None, None) => 0,
+        }
+    }
+
+    /// Selects a row by its 1-based number on screen, clamped to the last row
+    fn go_to_row(&self, row: usize) {
+        let last = self.row_count().saturating_sub(1);
+        let row = row.saturating_sub(1).min(last);
+        self.diff_state.borrow_mut().select(Some(row));
+    }
+
     fn go_to_last_row(&self) {
-        let last_row = self.diff.longest_diff_len();
-        self.diff_state
-            .old_diff()
-            .borrow_mut()
-            .select(Some(last_row));
-        self.diff_state
-            .current_diff()
-            .borrow_mut()
-            .select(Some(last_row));
+        let rows = self.row_count();
+        self.diff_state.borrow_mut().select_last(rows);
     }
 
     fn next_row(&self) {
-        let old_diff_row_index = match self.diff_state.old_diff().borrow().selected() {
-            Some(i) => {
-                if i >= self.diff.old_diff().len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
+        let rows = self.row_count();
+        self.diff_state.borrow_mut().select_next(rows);
+    }
+
+    fn previous_row(&self) {
+        let rows = self.row_count();
+        self.diff_state.borrow_mut().select_previous(rows);
+    }
+
+    fn tree_focused(&self) -> bool {
+        self.show_tree && self.focus == Focus::Tree
+    }
+
+    fn diff_focused(&self) -> bool {
+        !self.tree_focused() && self.focus != Focus::Console
+    }
+
+    /// Shows the selected file in the tree and moves focus to it, or collapses or expands the
+    /// selected directory
+    fn open_tree_entry(&mut self) {
+        if !self.tree_focused() {
+            return;
+        }
+        match self.tree.selected_entry().map(|entry| entry.kind().clone()) {
+            Some(TreeEntryKind::File(index)) => {
+                self.select_file(index);
+                self.focus = Focus::Diff;
             }
-            None => 0,
+            Some(TreeEntryKind::Directory(path)) => self.tree.toggle(&path),
+            None => {}
+        }
+    }
+
+    /// Selects the first row of where the moved block under the cursor came from or went to
+    fn go_to_moved_counterpart(&self) {
+        let Some(diff) = self.diff().filter(|_| self.conflict.is_none()) else {
+            return;
         };
+        let selected = self.diff_state.borrow().selected().unwrap_or(0);
 
-        let current_diff_row_index = match self.diff_state.current_diff().borrow().selected() {
-            Some(j) => {
-                if j >= self.diff.current_diff().len() - 1 {
-                    0
-                } else {
-                    j + 1
-                }
-            }
-            None => 0,
+        match diff.moved_counterpart(selected) {
+            Some(row) => self.diff_state.borrow_mut().select(Some(row)),
+            None => tracing::warn!("No moved block under the cursor"),
+        }
+    }
+
+    fn next_file(&mut self) {
+        if self.diffs.is_empty() {
+            return;
+        }
+        self.select_file((self.selected_file + 1) % self.diffs.len());
+    }
+
+    fn previous_file(&mut self) {
+        if self.diffs.is_empty() {
+            return;
+        }
+        let index = match self.selected_file {
+            0 => self.diffs.len() - 1,
+            i => i - 1,
         };
+        self.select_file(index);
+    }
+
+    fn select_file(&mut self, index: usize) {
+        self.selected_file = index;
+        self.tree.select_file(index);
+        self.diff_state.reset_row_state();
+        if let Some(path) = self.diff().and_then(|diff| diff.path()) {
+            tracing::info!("Showing {} ({}/{})", path, index + 1, self.diffs.len());
+        }
+    }
 
-        self.diff_state
-            .old_diff()
-            .borrow_mut()
-            .select(Some(old_diff_row_index));
-        self.diff_state
-            .current_diff()
-            .borrow_mut()
-            .select(Some(current_diff_row_index));
+    fn selected_conflict_row(&self) -> Option<usize> {
+        self.diff_state.borrow().selected()
     }
 
-    fn previous_row(&self) {
-        let old_diff_row_index = match self.diff_state.old_diff().borrow().selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.diff.old_diff().len() - 1
-                } else {
-                    i - 1
-                }
-            }
-            None => 0,
+    fn select_conflict_row(&self, row: usize) {
+        self.diff_state.borrow_mut().select(Some(row));
+    }
+
+    fn next_conflict(&self) {
+        let Some(conflict) = &self.conflict else {
+            return;
         };
+        let selected = self.selected_conflict_row().unwrap_or(0);
+        let regions = conflict.rows().regions();
 
-        let current_diff_row_index = match self.diff_state.current_diff().borrow().selected() {
-            Some(j) => {
-                if j == 0 {
-                    self.diff.current_diff().len() - 1
-                } else {
-                    j - 1
-                }
+        let next = regions
+            .iter()
+            .find(|region| region.start > selected)
+            .or_else(|| regions.first());
+
+        if let Some(region) = next {
+            self.select_conflict_row(region.start);
+        }
+    }
+
+    fn previous_conflict(&self) {
+        let Some(conflict) = &self.conflict else {
+            return;
+        };
+        let selected = self.selected_conflict_row().unwrap_or(0);
+        let regions = conflict.rows().regions();
+
+        let previous = regions
+            .iter()
+            .rev()
+            .find(|region| region.end <= selected)
+            .or_else(|| regions.last());
+
+        if let Some(region) = previous {
+            self.select_conflict_row(region.start);
+        }
+    }
+
+    fn resolve_conflict(&mut self, resolution: Resolution) {
+        let selected = self.selected_conflict_row().unwrap_or(0);
+        let Some(conflict) = self.conflict.as_mut() else {
+            return;
+        };
+
+        match conflict.conflict_at_row(selected) {
+            Some(index) => {
+                conflict.resolve(index, resolution);
+                tracing::info!(
+                    "Resolved conflict {} of {}, {} remaining",
+                    index + 1,
+                    conflict.conflict_count(),
+                    conflict.unresolved_count()
+                );
+                // Keep the cursor on a row of the rebuilt panes
+                let last = conflict.rows().len().saturating_sub(1);
+                self.select_conflict_row(selected.min(last));
             }
-            None => 0,
+            None => tracing::warn!("No conflict under the cursor"),
+        }
+    }
+
+    fn write_resolved(&self) {
+        let Some(conflict) = &self.conflict else {
+            return;
         };
 
-        self.diff_state
-            .old_diff()
-            .borrow_mut()
-            .select(Some(old_diff_row_index));
-        self.diff_state
-            .current_diff()
-            .borrow_mut()
-            .select(Some(current_diff_row_index));
+        match conflict.write() {
+            Ok(()) => tracing::info!(
+                "Wrote {} ({} unresolved conflicts)",
+                conflict.path().display(),
+                conflict.unresolved_count()
+            ),
+            Err(e) => tracing::error!("Failed to write {}: {}", conflict.path().display(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn app() -> App {
+        App::new(Default::default(), AppConfig::defaults().unwrap())
+    }
+
+    #[test]
+    fn test_count_repeats_motions_only() {
+        let mut app = app();
+        app.set_diffs(vec![Diff::from_texts("a\nb\nc\nd\ne\n", "a\nb\nc\nd\ne\n")]);
+
+        app.count = Some(3);
+        app.update(Message::NextRow);
+        assert_eq!(app.diff_state.borrow().selected(), Some(3));
+
+        app.count = Some(2);
+        app.update(Message::ToggleWhitespace);
+        assert!(app.show_whitespace());
+    }
+
+    #[test]
+    fn test_resolve_clamps_selection() {
+        let mut app = app();
+        let content = "one\n<<<<<<< HEAD\nours\n=======\ntheirs\nmore theirs\n>>>>>>> feature\n";
+        app.set_conflict(ConflictFile::parse(Path::new("file.txt"), content).unwrap());
+        app.update(Message::LastRow);
+        assert_eq!(app.selected_conflict_row(), Some(2));
+
+        app.update(Message::PickOurs);
+        let rows = app.conflict().unwrap().rows().len();
+        assert_eq!(app.conflict().unwrap().unresolved_count(), 0);
+        assert!(app.selected_conflict_row().is_some_and(|row| row < rows));
     }
 }
@@ -0,0 +1,164 @@
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+
+use crate::{diff::Diff, update::message::Message};
+
+/// The command palette: a query fuzzy-matched over every action and changed file
+#[derive(Debug, Default)]
+pub struct Palette {
+    query: String,
+    items: Vec<PaletteItem>,
+    /// Indexes into `items` matching the query, best match first
+    matches: Vec<usize>,
+    /// Index into `matches`
+    selected: usize,
+}
+
+/// Something the palette can run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteItem {
+    Action(Message),
+    /// Shows the file at `index` in the app's diffs
+    File {
+        index: usize,
+        path: String,
+    },
+}
+
+impl PaletteItem {
+    /// The text the query is matched against
+    pub fn label(&self) -> String {
+        match self {
+            PaletteItem::Action(message) => message.to_string(),
+            PaletteItem::File { path, .. } => path.clone(),
+        }
+    }
+}
+
+impl Palette {
+    pub fn new(diffs: &[Diff]) -> Self {
+        let actions = Message::ACTIONS
+            .into_iter()
+            .filter(|message| *message != Message::OpenPalette)
+            .map(PaletteItem::Action);
+        let files = diffs
+            .iter()
+            .enumerate()
+            .map(|(index, diff)| PaletteItem::File {
+                index,
+                path: diff.path().unwrap_or_default().to_string(),
+            });
+
+        let mut palette = Self {
+            items: actions.chain(files).collect(),
+            ..Default::default()
+        };
+        palette.filter();
+        palette
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.filter();
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.filter();
+    }
+
+    /// Items matching the query, best match first
+    pub fn matches(&self) -> Vec<&PaletteItem> {
+        self.matches
+            .iter()
+            .map(|&index| &self.items[index])
+            .collect()
+    }
+
+    /// Index of the selected item among the matches
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_item(&self) -> Option<&PaletteItem> {
+        self.matches
+            .get(self.selected)
+            .map(|&index| &self.items[index])
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + 1) % self.matches.len();
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        let len = self.matches.len();
+        if len > 0 {
+            self.selected = (self.selected + len - 1) % len;
+        }
+    }
+
+    /// Matches the items against the query, keeping their order for an empty query
+    fn filter(&mut self) {
+        let matcher = SkimMatcherV2::default().ignore_case();
+        let mut scored: Vec<(i64, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let score = matcher.fuzzy_match(&item.label(), &self.query)?;
+                Some((score, index))
+            })
+            .collect();
+        // Stable, so equal scores keep the items' order
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+
+        self.matches = scored.into_iter().map(|(_, index)| index).collect();
+        self.selected = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette() -> Palette {
+        let diffs = ["src/app/mod.rs", "readme.md"]
+            .map(|path| Diff::from_texts("a\n", "b\n").with_paths(Some(path), Some(path)));
+        Palette::new(&diffs)
+    }
+
+    #[test]
+    fn test_fuzzy_match_actions_and_files() {
+        let mut palette = palette();
+        assert_eq!(palette.matches().len(), Message::ACTIONS.len() - 1 + 2);
+
+        for c in "whitesp".chars() {
+            palette.push(c);
+        }
+        assert_eq!(
+            palette.selected_item(),
+            Some(&PaletteItem::Action(Message::ToggleWhitespace))
+        );
+
+        palette.query.clear();
+        for c in "appmod".chars() {
+            palette.push(c);
+        }
+        assert_eq!(
+            palette.selected_item(),
+            Some(&PaletteItem::File {
+                index: 0,
+                path: "src/app/mod.rs".to_string()
+            })
+        );
+
+        palette.push('z');
+        assert!(palette.matches().is_empty());
+        assert_eq!(palette.selected_item(), None);
+    }
+}
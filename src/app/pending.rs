@@ -0,0 +1,174 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    services::config::{Binding, KeyMap},
+    update::{keys::Key, message::Message},
+};
+
+/// How long to wait for the next key of a sequence, as vim's default `timeoutlen`
+pub const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Counts above this are clamped, so a held down digit can't queue an endless repeat
+const MAX_COUNT: usize = 9999;
+
+/// Keys typed towards a binding that isn't complete yet, with the count typed before them,
+/// e.g. `10` then `g` on the way to `10gg`
+#[derive(Debug, Default)]
+pub struct PendingKeys {
+    keys: Vec<String>,
+    count: Option<usize>,
+    /// When the last key was typed
+    typed_at: Option<Instant>,
+}
+
+/// A binding completed by the keys typed so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyCommand {
+    pub message: Message,
+    /// Typed before the keys, repeats the message or gives it a row number
+    pub count: Option<usize>,
+}
+
+impl PendingKeys {
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.count.is_none()
+    }
+
+    /// Adds a key, returning the message once a binding is complete. Digits typed before any
+    /// other key make up the count, `0` only after another digit. `esc` cancels pending keys.
+    pub fn push(&mut self, key: Key, keymap: &KeyMap, now: Instant) -> Option<KeyCommand> {
+        if key == Key::Esc && !self.is_empty() {
+            self.clear();
+            return None;
+        }
+        self.typed_at = Some(now);
+
+        if let Key::Char(c @ '0'..='9') = key {
+            if self.keys.is_empty() && (c != '0' || self.count.is_some()) {
+                let digit = c.to_digit(10).unwrap_or_default() as usize;
+                let count = self.count.unwrap_or_default() * 10 + digit;
+                self.count = Some(count.min(MAX_COUNT));
+                return None;
+            }
+        }
+
+        self.keys.push(key.to_string());
+        match keymap.binding(&self.keys) {
+            Binding::Message(message) => Some(self.complete(message)),
+            Binding::Prefix(_) => None,
+            Binding::Unbound => {
+                tracing::debug!("No binding for {}", self);
+                self.clear();
+                None
+            }
+        }
+    }
+
+    /// Resolves the pending keys once no key has been typed for [`SEQUENCE_TIMEOUT`], giving
+    /// the message of a binding that is also the start of a longer one, e.g. `]` with `] f`
+    pub fn expire(&mut self, keymap: &KeyMap, now: Instant) -> Option<KeyCommand> {
+        let typed_at = self.typed_at?;
+        if self.is_empty() || now.duration_since(typed_at) < SEQUENCE_TIMEOUT {
+            return None;
+        }
+
+        match keymap.binding(&self.keys) {
+            Binding::Message(message) | Binding::Prefix(Some(message)) if !self.keys.is_empty() => {
+                Some(self.complete(message))
+            }
+            _ => {
+                self.clear();
+                None
+            }
+        }
+    }
+
+    fn complete(&mut self, message: Message) -> KeyCommand {
+        let command = KeyCommand {
+            message,
+            count: self.count,
+        };
+        self.clear();
+        command
+    }
+
+    fn clear(&mut self) {
+        self.keys.clear();
+        self.count = None;
+        self.typed_at = None;
+    }
+}
+
+/// The count and keys typed so far, e.g. `10g`
+impl fmt::Display for PendingKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(count) = self.count {
+            write!(f, "{}", count)?;
+        }
+        write!(f, "{}", self.keys.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::config::AppConfig;
+
+    fn keymap() -> KeyMap {
+        AppConfig::defaults().unwrap().keymap().clone()
+    }
+
+    fn type_keys(pending: &mut PendingKeys, keys: &[Key], now: Instant) -> Option<KeyCommand> {
+        keys.iter()
+            .map(|&key| pending.push(key, &keymap(), now))
+            .last()
+            .flatten()
+    }
+
+    #[test]
+    fn test_sequence_with_count() {
+        let mut pending = PendingKeys::default();
+        let now = Instant::now();
+
+        assert_eq!(
+            type_keys(&mut pending, &[Key::Char('g'), Key::Char('g')], now),
+            Some(KeyCommand {
+                message: Message::FirstRow,
+                count: None
+            })
+        );
+
+        let keys = [Key::Char('1'), Key::Char('0'), Key::Char('j')];
+        assert_eq!(
+            type_keys(&mut pending, &keys, now),
+            Some(KeyCommand {
+                message: Message::NextRow,
+                count: Some(10)
+            })
+        );
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_and_timeout() {
+        let mut pending = PendingKeys::default();
+        let now = Instant::now();
+
+        type_keys(&mut pending, &[Key::Char('2'), Key::Char('g')], now);
+        assert_eq!(pending.to_string(), "2g");
+        assert_eq!(pending.push(Key::Esc, &keymap(), now), None);
+        assert!(pending.is_empty());
+
+        type_keys(&mut pending, &[Key::Char('g')], now);
+        assert_eq!(pending.expire(&keymap(), now), None);
+        assert_eq!(pending.expire(&keymap(), now + SEQUENCE_TIMEOUT), None);
+        assert!(pending.is_empty());
+
+        // Unbound sequences are dropped
+        type_keys(&mut pending, &[Key::Char('g'), Key::Char('x')], now);
+        assert!(pending.is_empty());
+    }
+}
@@ -1,12 +1,10 @@
 use std::cell::RefCell;
 
-use ratatui::widgets::TableState;
+use crate::widget::SideBySideDiffState;
 
+/// Row selection shared by every pane of the body
 #[derive(Debug)]
-pub struct DiffState {
-    old_diff: RefCell<TableState>,
-    current_diff: RefCell<TableState>,
-}
+pub struct DiffState(RefCell<SideBySideDiffState>);
 
 #[derive(Debug, Default, PartialEq, Eq)]
 pub enum RunningState {
@@ -15,26 +13,33 @@ pub enum RunningState {
     Done,
 }
 
+/// Which pane the navigation keys act on
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    #[default]
+    Diff,
+    Tree,
+    Console,
+}
+
 impl Default for DiffState {
     fn default() -> Self {
-        Self {
-            old_diff: RefCell::from(TableState::default().with_selected(0)),
-            current_diff: RefCell::from(TableState::default().with_selected(0)),
-        }
+        Self(RefCell::from(
+            SideBySideDiffState::default().with_selected(Some(0)),
+        ))
     }
 }
 
 impl DiffState {
-    pub fn old_diff(&self) -> &RefCell<TableState> {
-        &self.old_diff
+    pub fn borrow(&self) -> std::cell::Ref<'_, SideBySideDiffState> {
+        self.0.borrow()
     }
 
-    pub fn current_diff(&self) -> &RefCell<TableState> {
-        &self.current_diff
+    pub fn borrow_mut(&self) -> std::cell::RefMut<'_, SideBySideDiffState> {
+        self.0.borrow_mut()
     }
 
     pub fn reset_row_state(&self) {
-        self.old_diff.borrow_mut().select(Some(0));
-        self.current_diff.borrow_mut().select(Some(0));
+        self.0.borrow_mut().select_first();
     }
 }
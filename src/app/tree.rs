@@ -0,0 +1,222 @@
+use std::collections::HashSet;
+
+use crate::diff::Diff;
+
+/// The changed files grouped by directory, for the sidebar
+#[derive(Debug, Default)]
+pub struct FileTree {
+    entries: Vec<TreeEntry>,
+    /// Paths of the directories whose contents are hidden
+    collapsed: HashSet<String>,
+    /// Index into the visible entries
+    selected: usize,
+}
+
+/// A directory or changed file in the tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeEntry {
+    /// Number of directories above the entry
+    depth: usize,
+    /// The last path component
+    name: String,
+    kind: TreeEntryKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeEntryKind {
+    /// Full path of the directory
+    Directory(String),
+    /// Index of the file in the app's diffs
+    File(usize),
+}
+
+impl TreeEntry {
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> &TreeEntryKind {
+        &self.kind
+    }
+}
+
+impl FileTree {
+    /// Builds the tree from each diff's path, sorted by path component as `git diff` does
+    pub fn new(diffs: &[Diff]) -> Self {
+        let mut files: Vec<(Vec<&str>, usize)> = diffs
+            .iter()
+            .enumerate()
+            .map(|(index, diff)| (diff.path().unwrap_or_default().split('/').collect(), index))
+            .collect();
+        files.sort();
+
+        let mut entries = Vec::new();
+        let mut open: &[&str] = &[];
+        for (components, index) in &files {
+            let (name, directories) = components.split_last().unwrap_or((&"", &[]));
+            let common = open
+                .iter()
+                .zip(directories)
+                .take_while(|(open, directory)| open == directory)
+                .count();
+            for depth in common..directories.len() {
+                entries.push(TreeEntry {
+                    depth,
+                    name: directories[depth].to_string(),
+                    kind: TreeEntryKind::Directory(directories[..=depth].join("/")),
+                });
+            }
+            entries.push(TreeEntry {
+                depth: directories.len(),
+                name: name.to_string(),
+                kind: TreeEntryKind::File(*index),
+            });
+            open = directories;
+        }
+
+        Self {
+            entries,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries outside of collapsed directories, in display order
+    pub fn visible(&self) -> Vec<&TreeEntry> {
+        let mut hidden_below = None;
+        self.entries
+            .iter()
+            .filter(|entry| {
+                if hidden_below.is_some_and(|depth| entry.depth > depth) {
+                    return false;
+                }
+                hidden_below = match &entry.kind {
+                    TreeEntryKind::Directory(path) if self.collapsed.contains(path) => {
+                        Some(entry.depth)
+                    }
+                    _ => None,
+                };
+                true
+            })
+            .collect()
+    }
+
+    pub fn is_collapsed(&self, path: &str) -> bool {
+        self.collapsed.contains(path)
+    }
+
+    /// Index of the selected entry among the visible ones
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_entry(&self) -> Option<&TreeEntry> {
+        self.visible().get(self.selected).copied()
+    }
+
+    pub fn select_next(&mut self) {
+        let len = self.visible().len();
+        if len > 0 {
+            self.selected = (self.selected + 1) % len;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        let len = self.visible().len();
+        if len > 0 {
+            self.selected = (self.selected + len - 1) % len;
+        }
+    }
+
+    pub fn select_first(&mut self) {
+        self.selected = 0;
+    }
+
+    pub fn select_last(&mut self) {
+        self.selected = self.visible().len().saturating_sub(1);
+    }
+
+    /// Selects the entry of the file at `index` in the app's diffs, if it's visible
+    pub fn select_file(&mut self, index: usize) {
+        let position = self
+            .visible()
+            .iter()
+            .position(|entry| entry.kind == TreeEntryKind::File(index));
+        if let Some(position) = position {
+            self.selected = position;
+        }
+    }
+
+    /// Collapses or expands a directory, keeping it selected
+    pub fn toggle(&mut self, path: &str) {
+        if !self.collapsed.remove(path) {
+            self.collapsed.insert(path.to_string());
+        }
+        let position = self.visible().iter().position(
+            |entry| matches!(&entry.kind, TreeEntryKind::Directory(other) if other == path),
+        );
+        self.selected = position.unwrap_or(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> FileTree {
+        let diffs = ["src/main.rs", "readme.md", "src/app/mod.rs", "src/lib.rs"]
+            .map(|path| Diff::from_texts("a\n", "b\n").with_paths(Some(path), Some(path)));
+        FileTree::new(&diffs)
+    }
+
+    fn names(tree: &FileTree) -> Vec<String> {
+        tree.visible()
+            .iter()
+            .map(|entry| format!("{}{}", "  ".repeat(entry.depth()), entry.name()))
+            .collect()
+    }
+
+    #[test]
+    fn test_groups_files_by_directory() {
+        let tree = tree();
+        assert_eq!(
+            names(&tree),
+            [
+                "readme.md",
+                "src",
+                "  app",
+                "    mod.rs",
+                "  lib.rs",
+                "  main.rs"
+            ]
+        );
+        assert_eq!(tree.entries[3].kind(), &TreeEntryKind::File(2));
+    }
+
+    #[test]
+    fn test_collapse_directory() {
+        let mut tree = tree();
+        tree.toggle("src/app");
+        assert_eq!(
+            names(&tree),
+            ["readme.md", "src", "  app", "  lib.rs", "  main.rs"]
+        );
+
+        tree.toggle("src");
+        assert_eq!(names(&tree), ["readme.md", "src"]);
+        assert_eq!(tree.selected(), 1);
+
+        // Files in collapsed directories can't be selected
+        tree.select_file(0);
+        assert_eq!(tree.selected(), 1);
+        tree.select_file(1);
+        assert_eq!(tree.selected(), 0);
+    }
+}
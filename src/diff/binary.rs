@@ -0,0 +1,199 @@
+use serde::Serialize;
+use std::fmt::{self, Display, Write};
+
+use super::Diff;
+
+/// Binaries up to this size on both sides also get a side by side hex dump diff
+pub const HEX_DIFF_LIMIT: usize = 16 * 1024;
+
+/// Bytes shown on each row of a hex dump
+const HEX_ROW_LEN: usize = 16;
+
+/// What is known about a binary file's change, sizes are `None` for a missing side or when only
+/// git's "Binary files differ" line was available
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BinarySummary {
+    old_size: Option<u64>,
+    new_size: Option<u64>,
+    mime: Option<String>,
+}
+
+impl BinarySummary {
+    pub fn old_size(&self) -> Option<u64> {
+        self.old_size
+    }
+
+    pub fn new_size(&self) -> Option<u64> {
+        self.new_size
+    }
+
+    /// Content type guessed from the file's leading bytes
+    pub fn mime(&self) -> Option<&str> {
+        self.mime.as_deref()
+    }
+}
+
+impl Display for BinarySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Binary file")?;
+        if let Some(mime) = &self.mime {
+            write!(f, " ({})", mime)?;
+        }
+
+        match (self.old_size, self.new_size) {
+            (None, None) => write!(f, " differs"),
+            (Some(old), None) => write!(f, ", {} → deleted", format_size(old)),
+            (None, Some(new)) => write!(f, ", added → {}", format_size(new)),
+            (Some(old), Some(new)) => write!(f, ", {}", format_size_change(old, new)),
+        }
+    }
+}
+
+impl Diff {
+    /// Summarises two versions of a binary file, `None` marking an added or deleted side. Small
+    /// files are compared as hex dumps so the changed bytes can be browsed like text.
+    pub fn from_binary(old: Option<&[u8]>, new: Option<&[u8]>) -> Self {
+        let summary = BinarySummary {
+            old_size: old.map(|bytes| bytes.len() as u64),
+            new_size: new.map(|bytes| bytes.len() as u64),
+            mime: new.or(old).map(|bytes| guess_mime(bytes).to_string()),
+        };
+
+        let small = |side: Option<&[u8]>| side.map_or(0, <[u8]>::len) <= HEX_DIFF_LIMIT;
+        let mut diff = if small(old) && small(new) {
+            Diff::from_texts(
+                &hex_dump(old.unwrap_or_default()),
+                &hex_dump(new.unwrap_or_default()),
+            )
+        } else {
+            Diff::default()
+        };
+        diff.binary = Some(summary);
+        diff
+    }
+}
+
+/// Whether content looks binary, using git's heuristic of a NUL byte near the start
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|&byte| byte == 0)
+}
+
+/// Guesses a content type from well known file signatures
+fn guess_mime(bytes: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"BM", "image/bmp"),
+        (b"\x00\x00\x01\x00", "image/x-icon"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\x7fELF", "application/x-elf"),
+        (b"\x00asm", "application/wasm"),
+        (b"SQLite format 3\x00", "application/vnd.sqlite3"),
+    ];
+
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+
+    SIGNATURES
+        .iter()
+        .find(|(signature, _)| bytes.starts_with(signature))
+        .map_or("application/octet-stream", |(_, mime)| mime)
+}
+
+/// Formats a change in size as `old → new (±delta)`
+pub(crate) fn format_size_change(old: u64, new: u64) -> String {
+    let sign = if new < old { "-" } else { "+" };
+    format!(
+        "{} → {} ({}{})",
+        format_size(old),
+        format_size(new),
+        sign,
+        format_size(old.abs_diff(new))
+    )
+}
+
+/// Formats a byte count with binary units, e.g. `1.5 KiB`
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Renders bytes as `offset  hex bytes  |ascii|` rows, one per line
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (row, chunk) in bytes.chunks(HEX_ROW_LEN).enumerate() {
+        let _ = write!(dump, "{:08x} ", row * HEX_ROW_LEN);
+        for byte in chunk {
+            let _ = write!(dump, " {:02x}", byte);
+        }
+        let padding = (HEX_ROW_LEN - chunk.len()) * 3;
+        let ascii: String = chunk
+            .iter()
+            .map(|&byte| match byte {
+                0x20..=0x7e => byte as char,
+                _ => '.',
+            })
+            .collect();
+        let _ = writeln!(dump, "{:padding$}  |{}|", "", ascii);
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_binary_summary() {
+        let old = b"\x89PNG\r\n\x1a\n\x00\x01";
+        let new = b"\x89PNG\r\n\x1a\n\x00\x02\x03";
+        let diff = Diff::from_binary(Some(old), Some(new));
+
+        let summary = diff.binary().unwrap();
+        assert_eq!(summary.mime(), Some("image/png"));
+        assert_eq!(
+            summary.to_string(),
+            "Binary file (image/png), 10 B → 11 B (+1 B)"
+        );
+        assert_eq!(diff.removals(), 1);
+        assert!(diff.current_diff()[0]
+            .content()
+            .starts_with("00000000  89 50"));
+    }
+
+    #[test]
+    fn test_large_binary_has_no_rows() {
+        let new = vec![0; HEX_DIFF_LIMIT + 1];
+        let diff = Diff::from_binary(None, Some(&new));
+
+        assert!(diff.old_diff().is_empty() && diff.current_diff().is_empty());
+        assert!(!diff.is_empty());
+        assert_eq!(
+            diff.binary().unwrap().to_string(),
+            "Binary file (application/octet-stream), added → 16.0 KiB"
+        );
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
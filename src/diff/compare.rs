@@ -0,0 +1,157 @@
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+use super::{Diff, DiffKind, WhitespaceOptions};
+
+/// Diffs two texts in-process, for inputs that aren't tracked by git (e.g. difftool temp files)
+pub(super) fn diff_texts(old: &str, new: &str, whitespace: &WhitespaceOptions) -> Diff {
+    let mut diff = diff_lines(&split_lines(old), &split_lines(new), whitespace);
+    if whitespace.ignores_blank_lines() {
+        diff.ignore_blank_changes();
+    }
+    diff
+}
+
+/// Diffs two lists of lines, matching lines that only differ in ignored whitespace
+pub(super) fn diff_lines(
+    old_lines: &[&str],
+    new_lines: &[&str],
+    whitespace: &WhitespaceOptions,
+) -> Diff {
+    let mut diff = Diff::parse_diff(&unified_diff(old_lines, new_lines, whitespace));
+
+    // Unchanged lines were written from the new text, the old side keeps its own whitespace
+    for line in &mut diff.old_diff {
+        if let (DiffKind::Neutral, Some(number)) = (line.kind, line.line_number) {
+            if let Some(old_line) = old_lines.get(number - 1) {
+                if line.content != *old_line {
+                    line.content = old_line.to_string();
+                }
+            }
+        }
+    }
+    diff
+}
+
+/// Builds a unified diff of two texts with a single hunk covering the whole file, the same
+/// shape `git diff -U1000` gives for most files. Lines are matched by their normalised form,
+/// with matched lines written as context from the new text.
+fn unified_diff(old_lines: &[&str], new_lines: &[&str], whitespace: &WhitespaceOptions) -> String {
+    let old_keys: Vec<_> = old_lines
+        .iter()
+        .map(|line| whitespace.normalize(line))
+        .collect();
+    let new_keys: Vec<_> = new_lines
+        .iter()
+        .map(|line| whitespace.normalize(line))
+        .collect();
+    let ops = capture_diff_slices(Algorithm::Myers, &old_keys, &new_keys);
+
+    let mut hunk = vec![format!(
+        "@@ -{} +{} @@",
+        hunk_range(old_lines.len()),
+        hunk_range(new_lines.len())
+    )];
+
+    for op in ops {
+        match op {
+            DiffOp::Equal { new_index, len, .. } => {
+                for line in &new_lines[new_index..new_index + len] {
+                    hunk.push(format!(" {}", line));
+                }
+            }
+            DiffOp::Delete {
+                old_index, old_len, ..
+            } => {
+                for line in &old_lines[old_index..old_index + old_len] {
+                    hunk.push(format!("-{}", line));
+                }
+            }
+            DiffOp::Insert {
+                new_index, new_len, ..
+            } => {
+                for line in &new_lines[new_index..new_index + new_len] {
+                    hunk.push(format!("+{}", line));
+                }
+            }
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => {
+                for line in &old_lines[old_index..old_index + old_len] {
+                    hunk.push(format!("-{}", line));
+                }
+                for line in &new_lines[new_index..new_index + new_len] {
+                    hunk.push(format!("+{}", line));
+                }
+            }
+        }
+    }
+
+    hunk.join("\n")
+}
+
+/// Formats a whole-file hunk range, an empty file is `0,0` as in git's output
+fn hunk_range(len: usize) -> String {
+    if len == 0 {
+        "0,0".to_string()
+    } else {
+        format!("1,{}", len)
+    }
+}
+
+/// Splits text on `\n`, keeping any `\r` so line ending changes show up
+fn split_lines(text: &str) -> Vec<&str> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let text = text.strip_suffix('\n').unwrap_or(text);
+    text.split('\n').collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_texts() {
+        let diff = diff_texts(
+            "one\ntwo\nthree\n",
+            "one\n2\nthree\nfour\n",
+            &WhitespaceOptions::default(),
+        );
+
+        assert_eq!(diff.old_diff().len(), diff.current_diff().len());
+        assert_eq!(diff.current_diff()[1].content(), "2");
+        assert_eq!(diff.old_diff()[1].content(), "two");
+        assert_eq!(*diff.current_diff()[3].line_number(), Some(4));
+        assert_eq!(*diff.old_diff()[3].line_number(), None);
+    }
+
+    #[test]
+    fn test_diff_texts_from_empty() {
+        let diff = diff_texts("", "new\n", &WhitespaceOptions::default());
+
+        assert_eq!(diff.current_diff().len(), 1);
+        assert_eq!(*diff.current_diff()[0].line_number(), Some(1));
+    }
+
+    #[test]
+    fn test_diff_texts_ignoring_whitespace() {
+        let old = "fn main() {\r\n    run();\r\n}\r\n";
+        let new = "fn main() {\n\trun( );\n\n}\n";
+        let options = WhitespaceOptions::default()
+            .ignore_all_space(true)
+            .ignore_blank_lines(true);
+        let diff = diff_texts(old, new, &options);
+
+        assert_eq!(diff.additions(), 0);
+        assert_eq!(diff.removals(), 0);
+        assert_eq!(diff.current_diff()[1].content(), "\trun( );");
+
+        let diff = diff_texts(old, new, &WhitespaceOptions::default());
+        assert_eq!(diff.old_diff()[0].content(), "fn main() {\r");
+        assert_eq!(diff.removals(), 3);
+    }
+}
@@ -0,0 +1,449 @@
+//! The side by side diff model.
+//!
+//! A [`Diff`] holds one file's old and new lines aligned row by row: row `n` of
+//! [`Diff::old_diff`] sits alongside row `n` of [`Diff::current_diff`], with [`DiffKind::Blank`]
+//! padding wherever one side has no matching line. Diffs can be built from a unified diff or
+//! patch, or by comparing two texts in-process.
+//!
+//! ```
+//! use diff_tool::diff::{Diff, DiffKind};
+//!
+//! let diff = Diff::from_texts("one\ntwo\n", "one\n2\n").with_paths(Some("a.txt"), Some("a.txt"));
+//! assert_eq!(diff.additions(), 1);
+//! assert_eq!(*diff.current_diff()[1].kind(), DiffKind::Addition);
+//!
+//! let diffs = Diff::parse_patch("--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-two\n+2\n");
+//! assert_eq!(diffs[0].path(), Some("a.txt"));
+//! ```
+
+mod binary;
+mod compare;
+mod moved;
+mod parse;
+mod whitespace;
+
+pub(crate) use binary::{format_size, format_size_change};
+pub use binary::{is_binary, BinarySummary, HEX_DIFF_LIMIT};
+pub use moved::MovedBlock;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use std::ops::Range;
+pub use whitespace::WhitespaceOptions;
+
+/// One file's diff, with the old and new lines aligned row by row
+#[derive(Default, Debug)]
+pub struct Diff {
+    old_path: Option<String>,
+    new_path: Option<String>,
+    hunks: Vec<Hunk>,
+    old_diff: Vec<DiffLine>,
+    current_diff: Vec<DiffLine>,
+    binary: Option<BinarySummary>,
+    status: FileStatus,
+    /// File modes from git's extended headers, e.g. `100644`
+    old_mode: Option<String>,
+    new_mode: Option<String>,
+    /// Percentage of unchanged content for a rename or copy
+    similarity: Option<u8>,
+    /// Abbreviated blob ids from git's `index` header line
+    old_revision: Option<String>,
+    new_revision: Option<String>,
+    /// Name of the text encoding the file was decoded from, when not UTF-8
+    encoding: Option<String>,
+    moved: Vec<MovedBlock>,
+}
+
+impl Diff {
+    /// Compares two texts line by line, giving a single hunk that covers the whole file
+    pub fn from_texts(old: &str, new: &str) -> Self {
+        compare::diff_texts(old, new, &WhitespaceOptions::default())
+    }
+
+    /// Compares two texts like `from_texts`, treating lines that only differ in ignored
+    /// whitespace as unchanged
+    pub fn from_texts_with(old: &str, new: &str, whitespace: &WhitespaceOptions) -> Self {
+        compare::diff_texts(old, new, whitespace)
+    }
+
+    /// Labels the two sides, for diffs that didn't come with file headers. `None` marks an added
+    /// (old) or deleted (new) file.
+    pub fn with_paths(mut self, old_path: Option<&str>, new_path: Option<&str>) -> Self {
+        self.old_path = old_path.map(str::to_string);
+        self.new_path = new_path.map(str::to_string);
+        self.status = match (old_path, new_path) {
+            (None, Some(_)) => FileStatus::Added,
+            (Some(_), None) => FileStatus::Deleted,
+            _ => self.status,
+        };
+        self
+    }
+
+    /// Records the encoding the texts were decoded from, e.g. `windows-1252`
+    pub fn with_encoding(mut self, encoding: &str) -> Self {
+        self.encoding = Some(encoding.to_string());
+        self
+    }
+
+    /// Name of the text encoding, `None` for UTF-8
+    pub fn encoding(&self) -> Option<&str> {
+        self.encoding.as_deref()
+    }
+
+    /// Index of the last aligned row, 0 for an empty diff
+    pub fn longest_diff_len(&self) -> usize {
+        let old_diff = self.old_diff.len();
+        let current_diff = self.current_diff.len();
+
+        std::cmp::max(old_diff, current_diff).saturating_sub(1)
+    }
+
+    /// Path of the file before the change, `None` for an added file
+    pub fn old_path(&self) -> Option<&str> {
+        self.old_path.as_deref()
+    }
+
+    /// Path of the file after the change, `None` for a deleted file
+    pub fn new_path(&self) -> Option<&str> {
+        self.new_path.as_deref()
+    }
+
+    /// The most relevant path to show for this file
+    pub fn path(&self) -> Option<&str> {
+        self.new_path().or(self.old_path())
+    }
+
+    pub fn hunks(&self) -> &[Hunk] {
+        &self.hunks
+    }
+
+    /// Index of the hunk covering `row`, or of the last hunk before it when `row` falls between
+    /// hunks
+    pub fn hunk_at(&self, row: usize) -> Option<usize> {
+        self.hunks.iter().rposition(|hunk| hunk.rows.start <= row)
+    }
+
+    /// Whether there is anything to show. Binary files always have their summary, and renames
+    /// or mode changes are worth showing even without any changed lines.
+    pub fn is_empty(&self) -> bool {
+        self.old_diff.is_empty()
+            && self.current_diff.is_empty()
+            && self.binary.is_none()
+            && self.status == FileStatus::Modified
+            && self.old_mode == self.new_mode
+    }
+
+    pub fn status(&self) -> FileStatus {
+        self.status
+    }
+
+    pub fn old_mode(&self) -> Option<&str> {
+        self.old_mode.as_deref()
+    }
+
+    pub fn new_mode(&self) -> Option<&str> {
+        self.new_mode.as_deref()
+    }
+
+    /// Percentage of the file left unchanged by a rename or copy
+    pub fn similarity(&self) -> Option<u8> {
+        self.similarity
+    }
+
+    /// Blob id of the old version, for diffs made by git
+    pub fn old_revision(&self) -> Option<&str> {
+        self.old_revision.as_deref()
+    }
+
+    /// Blob id of the new version, for diffs made by git
+    pub fn new_revision(&self) -> Option<&str> {
+        self.new_revision.as_deref()
+    }
+
+    /// File level changes, e.g. `renamed, 90% similar, mode 100644 → 100755`. `None` for a
+    /// modified file that kept its mode.
+    pub fn change_description(&self) -> Option<String> {
+        let mut parts = Vec::new();
+
+        match self.status {
+            FileStatus::Modified => {}
+            FileStatus::Added => parts.push("added".to_string()),
+            FileStatus::Deleted => parts.push("deleted".to_string()),
+            FileStatus::Renamed | FileStatus::Copied => {
+                let status = match self.status {
+                    FileStatus::Renamed => "renamed",
+                    _ => "copied",
+                };
+                match self.similarity {
+                    Some(similarity) => parts.push(format!("{}, {}% similar", status, similarity)),
+                    None => parts.push(status.to_string()),
+                }
+            }
+        }
+
+        if let (Some(old_mode), Some(new_mode)) = (&self.old_mode, &self.new_mode) {
+            if old_mode != new_mode {
+                parts.push(format!("mode {} → {}", old_mode, new_mode));
+            }
+        }
+
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    /// Set for binary files, whose rows (if any) are a hex dump of their content
+    pub fn binary(&self) -> Option<&BinarySummary> {
+        self.binary.as_ref()
+    }
+
+    /// Lines of the original file, aligned with `current_diff`
+    pub fn old_diff(&self) -> &[DiffLine] {
+        &self.old_diff
+    }
+
+    /// Lines of the new file, aligned with `old_diff`
+    pub fn current_diff(&self) -> &[DiffLine] {
+        &self.current_diff
+    }
+
+    /// Width in characters of the largest line number on either side
+    pub fn largest_line_number_char_len(&self) -> u16 {
+        let (old_diff, current_diff) = self.largest_line_number();
+
+        let largest_line_number = std::cmp::max(old_diff, current_diff);
+
+        let length = std::cmp::min(largest_line_number.to_string().len(), u16::MAX.into());
+        length.try_into().unwrap_or(4)
+    }
+
+    /// Gets the largest line number from each diff
+    fn largest_line_number(&self) -> (usize, usize) {
+        let old_diff = self
+            .old_diff
+            .iter()
+            .map(|x| x.line_number().unwrap_or(0))
+            .max()
+            .unwrap_or(0);
+
+        let current_diff = self
+            .current_diff
+            .iter()
+            .map(|x| x.line_number().unwrap_or(0))
+            .max()
+            .unwrap_or(0);
+
+        (old_diff, current_diff)
+    }
+
+    /// Number of added lines
+    pub fn additions(&self) -> usize {
+        self.count_kind(&self.current_diff, DiffKind::Addition)
+            + self.count_kind(&self.current_diff, DiffKind::Moved)
+    }
+
+    /// Number of removed lines
+    pub fn removals(&self) -> usize {
+        self.count_kind(&self.old_diff, DiffKind::Removal)
+            + self.count_kind(&self.old_diff, DiffKind::Moved)
+    }
+
+    fn count_kind(&self, lines: &[DiffLine], kind: DiffKind) -> usize {
+        lines.iter().filter(|line| *line.kind() == kind).count()
+    }
+
+    /// Whether the diff is git's combined format (`diff --cc`), as produced for conflicted files
+    pub fn is_combined(diff_string: &str) -> bool {
+        diff_string
+            .lines()
+            .any(|line| line.starts_with("diff --cc ") || line.starts_with("@@@ "))
+    }
+
+    /// Parses the first file section of a unified diff
+    pub fn parse_diff(diff_string: &str) -> Self {
+        Self::parse_patch(diff_string)
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+    }
+
+    /// Parses every file section of a unified diff or patch, e.g. the output of `git diff` or
+    /// `git format-patch`. Anything before the first file header (such as email headers) is
+    /// ignored.
+    pub fn parse_patch(patch: &str) -> Vec<Self> {
+        parse::parse_patch(patch)
+    }
+
+    /// Splits the raw bytes of a unified diff into one slice per file, with anything before the
+    /// first file header kept in the first, so each file can be decoded with its own encoding
+    pub fn split_patch(patch: &[u8]) -> Vec<&[u8]> {
+        parse::split_patch(patch)
+    }
+}
+
+/// Serialises as the file paths, stats and hunks, followed by the aligned rows with the old and
+/// new line of each row side by side
+impl Serialize for Diff {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct AlignedRow<'a> {
+            old: Option<&'a DiffLine>,
+            new: Option<&'a DiffLine>,
+        }
+
+        let rows = (0..std::cmp::max(self.old_diff.len(), self.current_diff.len()))
+            .map(|row| AlignedRow {
+                old: self.old_diff.get(row),
+                new: self.current_diff.get(row),
+            })
+            .collect::<Vec<_>>();
+
+        let mut state = serializer.serialize_struct("Diff", 15)?;
+        state.serialize_field("old_path", &self.old_path)?;
+        state.serialize_field("new_path", &self.new_path)?;
+        state.serialize_field("status", &self.status)?;
+        state.serialize_field("old_mode", &self.old_mode)?;
+        state.serialize_field("new_mode", &self.new_mode)?;
+        state.serialize_field("similarity", &self.similarity)?;
+        state.serialize_field("old_revision", &self.old_revision)?;
+        state.serialize_field("new_revision", &self.new_revision)?;
+        state.serialize_field("encoding", &self.encoding)?;
+        state.serialize_field("additions", &self.additions())?;
+        state.serialize_field("removals", &self.removals())?;
+        state.serialize_field("binary", &self.binary)?;
+        state.serialize_field("hunks", &self.hunks)?;
+        state.serialize_field("moved", &self.moved)?;
+        state.serialize_field("rows", &rows)?;
+        state.end()
+    }
+}
+
+/// A `@@ -a,b +c,d @@` section of a file's diff
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Hunk {
+    old_start: usize,
+    old_len: usize,
+    new_start: usize,
+    new_len: usize,
+    /// Text following the header, usually the enclosing function
+    context: String,
+    /// Aligned rows of the diff covered by this hunk
+    rows: Range<usize>,
+}
+
+impl Hunk {
+    pub fn old_start(&self) -> usize {
+        self.old_start
+    }
+
+    pub fn old_len(&self) -> usize {
+        self.old_len
+    }
+
+    pub fn new_start(&self) -> usize {
+        self.new_start
+    }
+
+    pub fn new_len(&self) -> usize {
+        self.new_len
+    }
+
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+
+    /// Range of aligned row indices covered by this hunk
+    pub fn rows(&self) -> Range<usize> {
+        self.rows.clone()
+    }
+}
+
+/// A line on one side of a diff
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiffLine {
+    content: String,
+    kind: DiffKind,
+    line_number: Option<usize>,
+    /// Byte ranges of `content` that changed within the line
+    highlights: Vec<Range<usize>>,
+}
+
+impl DiffLine {
+    /// A line with its 1-based line number in its file, `None` for blank padding
+    pub fn new(content: &str, kind: DiffKind, line_number: Option<usize>) -> Self {
+        let content = content.to_string();
+        Self {
+            content,
+            kind,
+            line_number,
+            highlights: Vec::new(),
+        }
+    }
+
+    /// Byte ranges of the content that changed compared to the paired line on the other side
+    pub fn highlights(&self) -> &[Range<usize>] {
+        &self.highlights
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn kind(&self) -> &DiffKind {
+        &self.kind
+    }
+
+    pub fn line_number(&self) -> &Option<usize> {
+        &self.line_number
+    }
+}
+
+/// What happened to a file as a whole, from git's extended header lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    #[default]
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Copied,
+}
+
+impl FileStatus {
+    /// Single letter code as in `git status --short`, e.g. `M` for modified
+    pub fn letter(&self) -> char {
+        match self {
+            FileStatus::Modified => 'M',
+            FileStatus::Added => 'A',
+            FileStatus::Deleted => 'D',
+            FileStatus::Renamed => 'R',
+            FileStatus::Copied => 'C',
+        }
+    }
+}
+
+/// What happened to a line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Addition,
+    Removal,
+    Neutral,
+    /// Part of an unresolved merge conflict
+    Conflict,
+    /// Removed or added as part of a block moved elsewhere in the file
+    Moved,
+    #[default]
+    Blank,
+}
+
+impl DiffKind {
+    /// The prefix shown before a line of this kind, as in a unified diff
+    pub fn value(&self) -> &str {
+        match self {
+            DiffKind::Addition => "+",
+            DiffKind::Removal => "-",
+            DiffKind::Neutral => " ",
+            DiffKind::Conflict => "!",
+            DiffKind::Moved => "~",
+            DiffKind::Blank => " ",
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use serde::Serialize;
+use std::{collections::HashMap, ops::Range};
+
+use super::{Diff, DiffKind, DiffLine};
+
+/// Fewest alphanumeric characters a block of lines needs to count as moved, as in git's
+/// `--color-moved`, so that lone braces or blank lines aren't picked up
+const MIN_MOVED_CHARS: usize = 20;
+
+/// Removed lines that reappear unchanged as added lines elsewhere in the file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MovedBlock {
+    /// Rows of the removed lines, on the old side
+    old_rows: Range<usize>,
+    /// Rows of the added lines, on the new side
+    new_rows: Range<usize>,
+}
+
+impl MovedBlock {
+    pub fn old_rows(&self) -> Range<usize> {
+        self.old_rows.clone()
+    }
+
+    pub fn new_rows(&self) -> Range<usize> {
+        self.new_rows.clone()
+    }
+}
+
+impl Diff {
+    /// Blocks of lines moved within the file, drawn as [`DiffKind::Moved`]
+    pub fn moved_blocks(&self) -> &[MovedBlock] {
+        &self.moved
+    }
+
+    /// First row of the other end of the move when `row` is part of a moved block
+    pub fn moved_counterpart(&self, row: usize) -> Option<usize> {
+        self.moved.iter().find_map(|block| {
+            if block.old_rows.contains(&row) {
+                Some(block.new_rows.start)
+            } else if block.new_rows.contains(&row) {
+                Some(block.old_rows.start)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Marks runs of removed lines that match a run of added lines as moved, taking the longest
+    /// match for each run
+    pub(super) fn detect_moved(&mut self) {
+        let blocks = find_moved_blocks(&self.old_diff, &self.current_diff);
+
+        for block in &blocks {
+            for row in block.old_rows() {
+                self.old_diff[row].kind = DiffKind::Moved;
+                clear_highlights(&mut self.old_diff, &mut self.current_diff, row);
+            }
+            for row in block.new_rows() {
+                self.current_diff[row].kind = DiffKind::Moved;
+                clear_highlights(&mut self.old_diff, &mut self.current_diff, row);
+            }
+        }
+        self.moved = blocks;
+    }
+
+    /// Turns moved lines back into removals and additions, before the rows are rebuilt
+    pub(super) fn unmark_moved(&mut self) {
+        let sides = [
+            (&mut self.old_diff, DiffKind::Removal),
+            (&mut self.current_diff, DiffKind::Addition),
+        ];
+        for (lines, kind) in sides {
+            for line in lines.iter_mut().filter(|line| line.kind == DiffKind::Moved) {
+                line.kind = kind;
+            }
+        }
+        self.moved.clear();
+    }
+}
+
+fn find_moved_blocks(old: &[DiffLine], new: &[DiffLine]) -> Vec<MovedBlock> {
+    let rows_of = |lines: &[DiffLine], kind| -> Vec<usize> {
+        (0..lines.len())
+            .filter(|&row| lines[row].kind == kind)
+            .collect()
+    };
+    let removed = rows_of(old, DiffKind::Removal);
+    let added = rows_of(new, DiffKind::Addition);
+
+    // Indexes into `added` by content, blank lines can't start a block
+    let mut added_by_content: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, &row) in added.iter().enumerate() {
+        let content = new[row].content.as_str();
+        if !content.trim().is_empty() {
+            added_by_content.entry(content).or_default().push(index);
+        }
+    }
+
+    let mut used = vec![false; added.len()];
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < removed.len() {
+        let candidates = added_by_content
+            .get(old[removed[i]].content.as_str())
+            .map_or(&[][..], Vec::as_slice);
+
+        let mut best: Option<(usize, usize)> = None;
+        for &j in candidates {
+            let len = (0..)
+                .take_while(|&k| {
+                    i + k < removed.len()
+                        && j + k < added.len()
+                        && !used[j + k]
+                        && (k == 0
+                            || (removed[i + k] == removed[i + k - 1] + 1
+                                && added[j + k] == added[j + k - 1] + 1))
+                        && old[removed[i + k]].content == new[added[j + k]].content
+                })
+                .count();
+            if best.is_none_or(|(_, best_len)| len > best_len) {
+                best = Some((j, len));
+            }
+        }
+
+        match best {
+            Some((j, len))
+                if len > 0
+                    && moved_chars(&old[removed[i]..=removed[i + len - 1]]) >= MIN_MOVED_CHARS =>
+            {
+                used[j..j + len].fill(true);
+                blocks.push(MovedBlock {
+                    old_rows: removed[i]..removed[i + len - 1] + 1,
+                    new_rows: added[j]..added[j + len - 1] + 1,
+                });
+                i += len;
+            }
+            _ => i += 1,
+        }
+    }
+    blocks
+}
+
+fn moved_chars(lines: &[DiffLine]) -> usize {
+    lines
+        .iter()
+        .flat_map(|line| line.content.chars())
+        .filter(|c| c.is_alphanumeric())
+        .count()
+}
+
+/// Intraline highlights compare the two lines of a row, which no longer applies once either of
+/// them is moved
+fn clear_highlights(old: &mut [DiffLine], new: &mut [DiffLine], row: usize) {
+    for lines in [old, new] {
+        if let Some(line) = lines.get_mut(row) {
+            line.highlights.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOVED: &str = "fn parse_header(line: &str) {\n    read_paths(line);\n}\n";
+    const MAIN: &str =
+        "fn main() {\n    let args = Args::parse();\n    run(args);\n    exit();\n}\n";
+
+    #[test]
+    fn test_detect_moved_block() {
+        let old = format!("{}\n{}", MOVED, MAIN);
+        let new = format!("{}\n{}", MAIN, MOVED);
+        let diff = Diff::from_texts(&old, &new);
+        let blocks = diff.moved_blocks();
+
+        assert_eq!(blocks.len(), 1);
+        let block = &blocks[0];
+        assert_eq!(block.old_rows().len(), 3);
+        assert!(diff.old_diff()[block.old_rows()]
+            .iter()
+            .all(|line| *line.kind() == DiffKind::Moved));
+        assert_eq!(
+            diff.current_diff()[block.new_rows().start].content(),
+            diff.old_diff()[block.old_rows().start].content()
+        );
+        assert_eq!(diff.removals(), 4);
+        assert_eq!(
+            diff.moved_counterpart(block.new_rows().start),
+            Some(block.old_rows().start)
+        );
+    }
+
+    #[test]
+    fn test_short_lines_are_not_moved() {
+        let diff = Diff::from_texts("a\n}\nb\n", "b\n}\na\n");
+        assert!(diff.moved_blocks().is_empty());
+    }
+}
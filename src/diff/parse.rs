@@ -0,0 +1,555 @@
+use similar::{ChangeTag, TextDiff};
+use std::ops::Range;
+
+use super::{BinarySummary, Diff, DiffKind, DiffLine, FileStatus, Hunk};
+
+/// Parses every file section of a unified diff, see `Diff::parse_patch`
+pub(super) fn parse_patch(patch: &str) -> Vec<Diff> {
+    let mut diffs = Vec::new();
+    let mut current: Option<DiffBuilder> = None;
+    // A patch saved with CRLF line endings has them on every line, otherwise a `\r` belongs to
+    // the file's content
+    let crlf = patch
+        .split('\n')
+        .next()
+        .is_some_and(|line| line.ends_with('\r'));
+    let mut lines = patch.split('\n').peekable();
+
+    while let Some(line) = lines.next() {
+        let line = if crlf {
+            line.strip_suffix('\r').unwrap_or(line)
+        } else {
+            line
+        };
+
+        if let Some(builder) = current.as_mut().filter(|builder| builder.in_hunk()) {
+            builder.push_hunk_line(line);
+            continue;
+        }
+
+        if line.starts_with("diff ") {
+            diffs.extend(current.take().map(DiffBuilder::finish));
+            let mut builder = DiffBuilder {
+                has_git_header: true,
+                ..Default::default()
+            };
+            // Paths from the `diff --git` line are overridden by `---`/`+++` when present
+            if let Some((old_path, new_path)) = parse_git_header_paths(line) {
+                builder.diff.old_path = Some(old_path);
+                builder.diff.new_path = Some(new_path);
+            }
+            current = Some(builder);
+            continue;
+        }
+
+        if let Some(builder) = current
+            .as_mut()
+            .filter(|builder| builder.has_git_header && !builder.has_hunks)
+        {
+            if builder.parse_extended_header(line) {
+                continue;
+            }
+        }
+
+        if let Some(old_path) = line.strip_prefix("--- ") {
+            if lines.peek().is_some_and(|next| next.starts_with("+++ ")) {
+                // A `---`/`+++` pair starts a new file unless it belongs to a `diff` header
+                let belongs_to_header = current
+                    .as_ref()
+                    .is_some_and(|builder| builder.has_git_header && !builder.has_hunks);
+                if !belongs_to_header {
+                    diffs.extend(current.take().map(DiffBuilder::finish));
+                }
+
+                let builder = current.get_or_insert_with(Default::default);
+                let new_path = lines.next().unwrap_or_default();
+                let new_path = new_path.strip_suffix('\r').unwrap_or(new_path);
+                builder.diff.old_path = parse_header_path(old_path, "a/");
+                builder.diff.new_path = parse_header_path(&new_path[4..], "b/");
+                continue;
+            }
+        }
+
+        if let Some(paths) = line.strip_prefix("Binary files ") {
+            let builder = current.get_or_insert_with(Default::default);
+            if let Some((old_path, new_path)) = paths
+                .strip_suffix(" differ")
+                .and_then(|paths| paths.split_once(" and "))
+            {
+                builder.diff.old_path = parse_header_path(old_path, "a/");
+                builder.diff.new_path = parse_header_path(new_path, "b/");
+            }
+            builder.diff.binary = Some(BinarySummary::default());
+            continue;
+        }
+
+        if line == "GIT binary patch" {
+            current.get_or_insert_with(Default::default).diff.binary =
+                Some(BinarySummary::default());
+            continue;
+        }
+
+        if let Some(hunk) = Hunk::parse_header(line) {
+            current
+                .get_or_insert_with(Default::default)
+                .start_hunk(hunk);
+        }
+    }
+
+    diffs.extend(current.map(DiffBuilder::finish));
+    diffs
+}
+
+/// Splits the raw bytes of a unified diff into its file sections by the same rules as
+/// [`parse_patch`], see `Diff::split_patch`
+pub(super) fn split_patch(patch: &[u8]) -> Vec<&[u8]> {
+    let lines: Vec<&[u8]> = patch.split_inclusive(|&byte| byte == b'\n').collect();
+    let mut starts = vec![0];
+    let mut offset = 0;
+    // The first file's section starts at 0, taking anything before its header
+    let mut seen_file = false;
+    // After a `diff` line and before its first hunk, where `---`/`+++` belong to its header
+    let mut in_header = false;
+    let (mut old_remaining, mut new_remaining) = (0, 0);
+
+    let mut index = 0;
+    while let Some(&line) = lines.get(index) {
+        let start = offset;
+        offset += line.len();
+        index += 1;
+
+        if old_remaining > 0 || new_remaining > 0 {
+            match line.first() {
+                Some(b'+') => new_remaining -= new_remaining.min(1),
+                Some(b'-') => old_remaining -= old_remaining.min(1),
+                Some(b'\\') => {}
+                _ => {
+                    old_remaining -= old_remaining.min(1);
+                    new_remaining -= new_remaining.min(1);
+                }
+            }
+            continue;
+        }
+
+        if line.starts_with(b"diff ") {
+            if seen_file {
+                starts.push(start);
+            }
+            seen_file = true;
+            in_header = true;
+        } else if line.starts_with(b"--- ")
+            && lines
+                .get(index)
+                .is_some_and(|next| next.starts_with(b"+++ "))
+        {
+            if seen_file && !in_header {
+                starts.push(start);
+            }
+            seen_file = true;
+            offset += lines[index].len();
+            index += 1;
+        } else if let Some(hunk) = std::str::from_utf8(line)
+            .ok()
+            .and_then(|line| Hunk::parse_header(line.trim_end()))
+        {
+            in_header = false;
+            old_remaining = hunk.old_len;
+            new_remaining = hunk.new_len;
+        }
+    }
+
+    starts.push(patch.len());
+    starts
+        .windows(2)
+        .map(|range| &patch[range[0]..range[1]])
+        .collect()
+}
+
+impl Hunk {
+    fn parse_header(line: &str) -> Option<Self> {
+        let rest = line.strip_prefix("@@ -")?;
+        let (ranges, context) = rest.split_once(" @@")?;
+        let (old, new) = ranges.split_once(" +")?;
+        let (old_start, old_len) = parse_range(old)?;
+        let (new_start, new_len) = parse_range(new)?;
+
+        Some(Self {
+            old_start,
+            old_len,
+            new_start,
+            new_len,
+            context: context.trim().to_string(),
+            rows: 0..0,
+        })
+    }
+}
+
+/// Parses a `start,len` hunk range, the length defaults to 1 when omitted
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// Strips the `a/` or `b/` prefix and any trailing timestamp from a `---`/`+++` path,
+/// `/dev/null` (an added or deleted file) becomes `None`
+fn parse_header_path(path: &str, prefix: &str) -> Option<String> {
+    let path = path.split('\t').next().unwrap_or(path);
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+/// Splits `diff --git a/old b/new` into its two paths
+fn parse_git_header_paths(line: &str) -> Option<(String, String)> {
+    let rest = line.strip_prefix("diff --git ")?;
+    let (old_path, new_path) = rest.split_once(" b/")?;
+    let old_path = old_path.strip_prefix("a/").unwrap_or(old_path);
+    Some((old_path.to_string(), new_path.to_string()))
+}
+
+/// Accumulates the aligned old and new sides of a single file while parsing
+#[derive(Debug, Default)]
+struct DiffBuilder {
+    diff: Diff,
+    has_git_header: bool,
+    has_hunks: bool,
+    old_line: usize,
+    new_line: usize,
+    old_remaining: usize,
+    new_remaining: usize,
+    additions: usize,
+    removals: usize,
+}
+
+impl DiffBuilder {
+    /// Reads a git extended header line (`new file mode`, `rename from`, ...) into the file's
+    /// metadata, returning whether the line was one
+    fn parse_extended_header(&mut self, line: &str) -> bool {
+        let diff = &mut self.diff;
+
+        if let Some(mode) = line.strip_prefix("old mode ") {
+            diff.old_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("new mode ") {
+            diff.new_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+            diff.status = FileStatus::Deleted;
+            diff.old_mode = Some(mode.to_string());
+            diff.new_path = None;
+        } else if let Some(mode) = line.strip_prefix("new file mode ") {
+            diff.status = FileStatus::Added;
+            diff.new_mode = Some(mode.to_string());
+            diff.old_path = None;
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            diff.status = FileStatus::Renamed;
+            diff.old_path = Some(path.to_string());
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            diff.status = FileStatus::Renamed;
+            diff.new_path = Some(path.to_string());
+        } else if let Some(path) = line.strip_prefix("copy from ") {
+            diff.status = FileStatus::Copied;
+            diff.old_path = Some(path.to_string());
+        } else if let Some(path) = line.strip_prefix("copy to ") {
+            diff.status = FileStatus::Copied;
+            diff.new_path = Some(path.to_string());
+        } else if let Some(index) = line.strip_prefix("index ") {
+            // `index <old>..<new> [<mode>]`, abbreviated blob ids
+            let revisions = index.split(' ').next().unwrap_or_default();
+            if let Some((old, new)) = revisions.split_once("..") {
+                diff.old_revision = Some(old.to_string());
+                diff.new_revision = Some(new.to_string());
+            }
+        } else if let Some(similarity) = line.strip_prefix("similarity index ") {
+            diff.similarity = similarity.trim_end_matches('%').parse().ok();
+        } else {
+            return false;
+        }
+        true
+    }
+
+    fn in_hunk(&self) -> bool {
+        self.old_remaining > 0 || self.new_remaining > 0
+    }
+
+    fn start_hunk(&mut self, mut hunk: Hunk) {
+        self.flush_blanks();
+        self.end_hunk();
+        self.has_hunks = true;
+        self.old_line = hunk.old_start.max(1);
+        self.new_line = hunk.new_start.max(1);
+        self.old_remaining = hunk.old_len;
+        self.new_remaining = hunk.new_len;
+
+        let row = self.diff.old_diff.len();
+        hunk.rows = row..row;
+        self.diff.hunks.push(hunk);
+    }
+
+    /// Closes the row range of the latest hunk at the current row
+    fn end_hunk(&mut self) {
+        let row = self.diff.old_diff.len();
+        if let Some(hunk) = self.diff.hunks.last_mut() {
+            hunk.rows.end = row;
+        }
+    }
+
+    fn push_hunk_line(&mut self, line: &str) {
+        let (prefix, content) = remove_first_char(line);
+
+        match prefix {
+            '+' => {
+                self.diff.current_diff.push(DiffLine::new(
+                    content,
+                    DiffKind::Addition,
+                    Some(self.new_line),
+                ));
+                self.new_line += 1;
+                self.new_remaining = self.new_remaining.saturating_sub(1);
+                if self.removals > 0 {
+                    self.removals -= 1
+                } else {
+                    self.additions += 1
+                }
+            }
+            '-' => {
+                self.diff.old_diff.push(DiffLine::new(
+                    content,
+                    DiffKind::Removal,
+                    Some(self.old_line),
+                ));
+                self.old_line += 1;
+                self.old_remaining = self.old_remaining.saturating_sub(1);
+                self.removals += 1
+            }
+            // "\ No newline at end of file"
+            '\\' => {}
+            _ => {
+                self.flush_blanks();
+
+                self.diff.old_diff.push(DiffLine::new(
+                    content,
+                    DiffKind::Neutral,
+                    Some(self.old_line),
+                ));
+                self.old_line += 1;
+                self.old_remaining = self.old_remaining.saturating_sub(1);
+                self.diff.current_diff.push(DiffLine::new(
+                    content,
+                    DiffKind::Neutral,
+                    Some(self.new_line),
+                ));
+                self.new_line += 1;
+                self.new_remaining = self.new_remaining.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Pads the shorter side of a block of changes with blank lines to keep both sides aligned
+    fn flush_blanks(&mut self) {
+        for _ in 0..self.removals {
+            self.diff
+                .current_diff
+                .push(DiffLine::new("", DiffKind::Blank, None))
+        }
+
+        self.removals = 0;
+
+        for _ in 0..self.additions {
+            self.diff
+                .old_diff
+                .push(DiffLine::new("", DiffKind::Blank, None))
+        }
+
+        self.additions = 0;
+    }
+
+    fn finish(mut self) -> Diff {
+        self.flush_blanks();
+        self.end_hunk();
+        highlight_changed_pairs(&mut self.diff);
+        self.diff.detect_moved();
+        self.diff
+    }
+}
+
+/// Marks the changed characters of each removed line that sits alongside an added line
+fn highlight_changed_pairs(diff: &mut Diff) {
+    for (old, current) in diff.old_diff.iter_mut().zip(diff.current_diff.iter_mut()) {
+        if old.kind != DiffKind::Removal || current.kind != DiffKind::Addition {
+            continue;
+        }
+
+        let (old_highlights, current_highlights) =
+            intraline_highlights(&old.content, &current.content);
+        old.highlights = old_highlights;
+        current.highlights = current_highlights;
+    }
+}
+
+/// Below this similarity a pair of lines is treated as a full rewrite, with nothing highlighted
+const INTRALINE_MIN_RATIO: f32 = 0.5;
+
+/// Byte ranges that differ between two lines, for the old and new line respectively
+fn intraline_highlights(old: &str, new: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let text_diff = TextDiff::from_chars(old, new);
+    if text_diff.ratio() < INTRALINE_MIN_RATIO {
+        return (Vec::new(), Vec::new());
+    }
+
+    let mut old_highlights: Vec<Range<usize>> = Vec::new();
+    let mut new_highlights: Vec<Range<usize>> = Vec::new();
+    let (mut old_offset, mut new_offset) = (0, 0);
+
+    for change in text_diff.iter_all_changes() {
+        let len = change.value().len();
+        match change.tag() {
+            ChangeTag::Equal => {
+                old_offset += len;
+                new_offset += len;
+            }
+            ChangeTag::Delete => {
+                push_range(&mut old_highlights, old_offset..old_offset + len);
+                old_offset += len;
+            }
+            ChangeTag::Insert => {
+                push_range(&mut new_highlights, new_offset..new_offset + len);
+                new_offset += len;
+            }
+        }
+    }
+
+    (old_highlights, new_highlights)
+}
+
+/// Pushes a range, merging it into the previous one when they touch
+fn push_range(ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+    match ranges.last_mut() {
+        Some(last) if last.end == range.start => last.end = range.end,
+        _ => ranges.push(range),
+    }
+}
+
+fn remove_first_char(string: &str) -> (char, &str) {
+    let mut chars = string.chars();
+    (chars.next().unwrap_or(' '), chars.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = "\
+From 1234 Mon Sep 17 00:00:00 2001
+Subject: [PATCH] Example
+
+diff --git a/src/one.rs b/src/one.rs
+index 1111111..2222222 100644
+--- a/src/one.rs
++++ b/src/one.rs
+@@ -1,3 +1,3 @@ fn main() {
+ first
+-second
++changed
+ third
+@@ -10,2 +10,3 @@
+ tenth
++added
+ eleventh
+diff --git a/src/two.rs b/src/two.rs
+new file mode 100644
+--- /dev/null
++++ b/src/two.rs
+@@ -0,0 +1 @@
++--- not a header
+";
+
+    #[test]
+    fn test_parse_patch_file_metadata() {
+        let patch = "\
+diff --git a/old.rs b/new.rs
+old mode 100644
+new mode 100755
+similarity index 90%
+rename from old.rs
+rename to new.rs
+diff --git a/gone.rs b/gone.rs
+deleted file mode 100644
+";
+        let diffs = Diff::parse_patch(patch);
+
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].status(), FileStatus::Renamed);
+        assert_eq!(diffs[0].old_path(), Some("old.rs"));
+        assert_eq!(diffs[0].new_path(), Some("new.rs"));
+        assert_eq!(
+            diffs[0].change_description().as_deref(),
+            Some("renamed, 90% similar, mode 100644 → 100755")
+        );
+        assert!(!diffs[0].is_empty());
+        assert_eq!(diffs[1].status(), FileStatus::Deleted);
+        assert_eq!(diffs[1].new_path(), None);
+    }
+
+    #[test]
+    fn test_parse_patch_binary_files() {
+        let patch = "diff --git a/logo.png b/logo.png\nindex 1234567..89abcde 100644\nBinary files a/logo.png and b/logo.png differ\n";
+        let diffs = Diff::parse_patch(patch);
+
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path(), Some("logo.png"));
+        assert_eq!(diffs[0].binary(), Some(&BinarySummary::default()));
+        assert!(!diffs[0].is_empty());
+    }
+
+    #[test]
+    fn test_parse_patch_splits_files() {
+        let diffs = Diff::parse_patch(PATCH);
+
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].path(), Some("src/one.rs"));
+        assert_eq!(diffs[0].old_revision(), Some("1111111"));
+        assert_eq!(diffs[0].new_revision(), Some("2222222"));
+        assert_eq!(diffs[1].old_path(), None);
+        assert_eq!(diffs[1].new_path(), Some("src/two.rs"));
+        assert_eq!(diffs[1].current_diff()[0].content(), "--- not a header");
+    }
+
+    #[test]
+    fn test_split_patch_matches_parse_patch() {
+        let sections = Diff::split_patch(PATCH.as_bytes());
+
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections.concat(), PATCH.as_bytes());
+        for (section, diff) in sections.iter().zip(Diff::parse_patch(PATCH)) {
+            let parsed = Diff::parse_diff(std::str::from_utf8(section).unwrap());
+            assert_eq!(parsed.path(), diff.path());
+            assert_eq!(parsed.additions(), diff.additions());
+            assert_eq!(parsed.hunks().len(), diff.hunks().len());
+        }
+    }
+
+    #[test]
+    fn test_parse_patch_line_numbers_follow_hunks() {
+        let diff = &Diff::parse_patch(PATCH)[0];
+
+        assert_eq!(diff.old_diff().len(), diff.current_diff().len());
+        assert_eq!(*diff.old_diff()[3].line_number(), Some(10));
+        assert_eq!(*diff.current_diff()[4].line_number(), Some(11));
+        assert_eq!(diff.current_diff()[4].content(), "added");
+        assert_eq!(diff.hunks().len(), 2);
+        assert_eq!(diff.hunks()[0].context(), "fn main() {");
+        assert_eq!(diff.hunks()[1].rows(), 3..6);
+        assert_eq!(diff.hunk_at(1), Some(0));
+        assert_eq!(diff.hunk_at(5), Some(1));
+    }
+
+    #[test]
+    fn test_parse_patch_highlights_changed_characters() {
+        let diff = Diff::parse_diff("@@ -1 +1 @@\n-let value = 1;\n+let value = 42;\n");
+
+        assert_eq!(diff.old_diff()[0].highlights().to_vec(), vec![12..13]);
+        assert_eq!(diff.current_diff()[0].highlights().to_vec(), vec![12..14]);
+    }
+}
@@ -0,0 +1,248 @@
+use std::borrow::Cow;
+
+use super::{compare, Diff, DiffKind, DiffLine};
+
+/// Whitespace differences to ignore when comparing lines, mirroring git's `-w`, `-b`,
+/// `--ignore-blank-lines` and `--ignore-cr-at-eol`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WhitespaceOptions {
+    ignore_all_space: bool,
+    ignore_space_change: bool,
+    ignore_blank_lines: bool,
+    ignore_eol: bool,
+}
+
+impl WhitespaceOptions {
+    /// Ignores all whitespace, e.g. `a = b` matches `a=b`
+    pub fn ignore_all_space(mut self, ignore: bool) -> Self {
+        self.ignore_all_space = ignore;
+        self
+    }
+
+    /// Ignores changes in the amount of whitespace, e.g. `a  = b` matches `a = b `
+    pub fn ignore_space_change(mut self, ignore: bool) -> Self {
+        self.ignore_space_change = ignore;
+        self
+    }
+
+    /// Ignores blocks of changes whose lines are all blank
+    pub fn ignore_blank_lines(mut self, ignore: bool) -> Self {
+        self.ignore_blank_lines = ignore;
+        self
+    }
+
+    /// Ignores a carriage return at the end of a line, so CRLF matches LF
+    pub fn ignore_eol(mut self, ignore: bool) -> Self {
+        self.ignore_eol = ignore;
+        self
+    }
+
+    pub fn ignores_blank_lines(&self) -> bool {
+        self.ignore_blank_lines
+    }
+
+    /// Whether nothing is ignored
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The equivalent `git diff` flags
+    pub fn git_args(&self) -> Vec<&'static str> {
+        [
+            (self.ignore_all_space, "--ignore-all-space"),
+            (self.ignore_space_change, "--ignore-space-change"),
+            (self.ignore_blank_lines, "--ignore-blank-lines"),
+            (self.ignore_eol, "--ignore-cr-at-eol"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, arg)| enabled.then_some(arg))
+        .collect()
+    }
+
+    /// The form of a line that is compared, two lines match when their keys are equal
+    pub fn normalize<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let line = if self.ignore_eol {
+            line.strip_suffix('\r').unwrap_or(line)
+        } else {
+            line
+        };
+
+        if self.ignore_all_space {
+            Cow::Owned(line.chars().filter(|c| !c.is_whitespace()).collect())
+        } else if self.ignore_space_change {
+            Cow::Owned(line.split_whitespace().collect::<Vec<_>>().join(" "))
+        } else {
+            Cow::Borrowed(line)
+        }
+    }
+}
+
+impl Diff {
+    /// Compares each block of changed rows again, so lines that only differ in ignored
+    /// whitespace become unchanged rows. For diffs that weren't made with the options, e.g.
+    /// patches.
+    pub fn ignore_whitespace(mut self, options: &WhitespaceOptions) -> Self {
+        if options.is_empty() {
+            return self;
+        }
+
+        let old_diff = std::mem::take(&mut self.old_diff);
+        let current_diff = std::mem::take(&mut self.current_diff);
+        // Where each of the previous rows starts in the new rows, for moving the hunk ranges
+        let mut row_starts = Vec::with_capacity(old_diff.len() + 1);
+
+        let mut row = 0;
+        while row < old_diff.len() {
+            if !is_changed_row(&old_diff[row], &current_diff[row]) {
+                row_starts.push(self.old_diff.len());
+                self.old_diff.push(old_diff[row].clone());
+                self.current_diff.push(current_diff[row].clone());
+                row += 1;
+                continue;
+            }
+
+            let start = row;
+            while row < old_diff.len() && is_changed_row(&old_diff[row], &current_diff[row]) {
+                row += 1;
+            }
+            row_starts.extend(std::iter::repeat_n(self.old_diff.len(), row - start));
+            self.push_block(&old_diff[start..row], &current_diff[start..row], options);
+        }
+        row_starts.push(self.old_diff.len());
+
+        for hunk in &mut self.hunks {
+            hunk.rows = row_starts[hunk.rows.start]..row_starts[hunk.rows.end];
+        }
+        // Blocks of changes were compared on their own, moves between them are found again
+        self.unmark_moved();
+        if options.ignore_blank_lines {
+            self.ignore_blank_changes();
+        }
+        self.detect_moved();
+        self
+    }
+
+    /// Appends the rows of a block of changes compared again with `options`
+    fn push_block(&mut self, old: &[DiffLine], new: &[DiffLine], options: &WhitespaceOptions) {
+        let lines = |lines: &[DiffLine], kind| -> (Vec<String>, usize) {
+            let lines: Vec<&DiffLine> = lines
+                .iter()
+                .filter(|line| line.kind == kind || line.kind == DiffKind::Moved)
+                .collect();
+            let first = lines.first().and_then(|line| line.line_number).unwrap_or(1);
+            (
+                lines.iter().map(|line| line.content.clone()).collect(),
+                first - 1,
+            )
+        };
+        let (old_lines, old_offset) = lines(old, DiffKind::Removal);
+        let (new_lines, new_offset) = lines(new, DiffKind::Addition);
+
+        let block = compare::diff_lines(
+            &old_lines.iter().map(String::as_str).collect::<Vec<_>>(),
+            &new_lines.iter().map(String::as_str).collect::<Vec<_>>(),
+            options,
+        );
+        let offset = |mut line: DiffLine, offset: usize| {
+            line.line_number = line.line_number.map(|number| number + offset);
+            line
+        };
+        self.old_diff.extend(
+            block
+                .old_diff
+                .into_iter()
+                .map(|line| offset(line, old_offset)),
+        );
+        self.current_diff.extend(
+            block
+                .current_diff
+                .into_iter()
+                .map(|line| offset(line, new_offset)),
+        );
+    }
+
+    /// Marks each run of changed rows as unchanged when all of its lines are blank
+    pub(super) fn ignore_blank_changes(&mut self) {
+        let is_blank =
+            |line: &DiffLine| line.kind == DiffKind::Blank || line.content.trim().is_empty();
+
+        let mut row = 0;
+        while row < self.old_diff.len() {
+            let changed = |row: usize| is_changed_row(&self.old_diff[row], &self.current_diff[row]);
+            if !changed(row) {
+                row += 1;
+                continue;
+            }
+
+            let start = row;
+            while row < self.old_diff.len() && changed(row) {
+                row += 1;
+            }
+            let all_blank = (start..row)
+                .all(|row| is_blank(&self.old_diff[row]) && is_blank(&self.current_diff[row]));
+            if all_blank {
+                for line in self.old_diff[start..row]
+                    .iter_mut()
+                    .chain(&mut self.current_diff[start..row])
+                    .filter(|line| line.kind != DiffKind::Blank)
+                {
+                    line.kind = DiffKind::Neutral;
+                    line.highlights.clear();
+                }
+            }
+        }
+    }
+}
+
+fn is_changed_row(old: &DiffLine, new: &DiffLine) -> bool {
+    matches!(old.kind, DiffKind::Removal | DiffKind::Moved)
+        || matches!(new.kind, DiffKind::Addition | DiffKind::Moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let all = WhitespaceOptions::default().ignore_all_space(true);
+        assert_eq!(all.normalize(" a = b\t"), "a=b");
+
+        let change = WhitespaceOptions::default().ignore_space_change(true);
+        assert_eq!(change.normalize("a  =\tb "), "a = b");
+
+        let eol = WhitespaceOptions::default().ignore_eol(true);
+        assert_eq!(eol.normalize("a \r"), "a ");
+        assert_eq!(eol.git_args(), ["--ignore-cr-at-eol"]);
+    }
+
+    #[test]
+    fn test_ignore_whitespace_in_patch() {
+        let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,3 @@\n-a  = b\n+a = b\n+\n x\n";
+        let options = WhitespaceOptions::default()
+            .ignore_space_change(true)
+            .ignore_blank_lines(true);
+        let diff = Diff::parse_patch(patch)
+            .remove(0)
+            .ignore_whitespace(&options);
+
+        assert_eq!(diff.additions(), 0);
+        assert_eq!(diff.removals(), 0);
+        assert_eq!(diff.old_diff()[0].content(), "a  = b");
+        assert_eq!(*diff.current_diff()[1].kind(), DiffKind::Neutral);
+        assert_eq!(*diff.old_diff()[1].kind(), DiffKind::Blank);
+    }
+
+    #[test]
+    fn test_ignore_whitespace_realigns_rows() {
+        let patch = "@@ -1,2 +1,3 @@\n-a = 1\n-b = 2\n+a  =  1\n+\n+b = 2  \n";
+        let options = WhitespaceOptions::default().ignore_space_change(true);
+        let diff = Diff::parse_diff(patch).ignore_whitespace(&options);
+
+        assert_eq!(diff.additions(), 1);
+        assert_eq!(diff.removals(), 0);
+        assert_eq!(diff.old_diff()[2].content(), "b = 2");
+        assert_eq!(*diff.current_diff()[2].line_number(), Some(3));
+        assert_eq!(diff.hunks()[0].rows, 0..3);
+    }
+}
@@ -1,4 +1,17 @@
+//! A side by side git diff viewer for the terminal.
+//!
+//! The [`diff`] and [`widget`] modules are the supported library surface, for building aligned
+//! side by side diffs from texts or unified patches and drawing them with ratatui. The remaining modules make up the `diff-tool` binary and
+//! may change between releases.
+
+pub mod diff;
+pub mod widget;
+
+#[doc(hidden)]
 pub mod app;
+#[doc(hidden)]
 pub mod services;
+#[doc(hidden)]
 pub mod update;
+#[doc(hidden)]
 pub mod view;
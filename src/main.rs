@@ -1,39 +1,88 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::Parser;
 use diff_tool::{
     app::{state::RunningState, App},
     services::{
-        cli::Args,
-        git::get_raw_diff,
-        logger::{initialize_logging, VecWriter},
+        cli::{Args, Command, ConfigAction, ExportFormat, OutputFormat},
+        config::{find_project_config, get_config_path, AppConfig, PROJECT_CONFIG},
+        json,
+        logger::{initialize_logging, ConsoleLayer, Logs},
+        source::Content,
         terminal,
     },
-    view,
+    view::{self, image::GraphicsLayer},
+    widget::Theme,
 };
-use std::sync::{Arc, Mutex};
+use std::{io::Write, path::Path};
 
 fn main() -> Result<()> {
+    let args = Args::parse();
+
+    if let Some(command) = args.command() {
+        return run_command(command);
+    }
+
+    let source = args.source()?;
+    let config = AppConfig::new(source.project_dir().as_deref(), args.config_overrides())?;
+    let options = config.options().clone();
+
     // Set up logging that can be sent to the application console
-    let logs = Arc::new(Mutex::new(Vec::new()));
-    let writer = VecWriter::new(logs.clone());
-    // init_logging(writer, log::LevelFilter::Trace);
-    initialize_logging(writer)?;
+    let logs = Logs::default();
+    initialize_logging(ConsoleLayer::new(logs.clone()), &options)?;
+    let mut app = App::new(logs, config);
 
-    let args = Args::parse();
+    match source.load(&options.diff_options()?)? {
+        Content::Diffs(diffs) => {
+            let images = diffs
+                .iter()
+                .filter_map(|diff| Some((diff.path()?.to_string(), source.load_image(diff)?)))
+                .collect();
+            app.set_images(images);
+            app.set_diffs(diffs);
+        }
+        Content::Conflict(conflict) => app.set_conflict(conflict),
+    }
+
+    if app.diff().is_none() && app.conflict().is_none() {
+        // Exit programme gracefully when no diff is found, `--format` still writing an empty
+        // document for tooling reading stdout
+        eprintln!("No diff found, exiting");
+        if args.format().is_none() {
+            return Ok(());
+        }
+    }
 
-    let mut app = App::new(logs);
+    if let Some(format) = args.format() {
+        let output = match format {
+            OutputFormat::Json => {
+                if app.conflict().is_some() {
+                    bail!("JSON output isn't available for files with merge conflicts");
+                }
+                json::to_json(app.diffs())?
+            }
+        };
+        write_stdout(&output)?;
+        return Ok(());
+    }
 
-    let diff_string = get_raw_diff(args.path(), args.change_dir());
-    app.set_diff(&diff_string);
+    if let Some((format, path)) = args.export()? {
+        let output = match format {
+            ExportFormat::Html => view::html::render_html(&app, &Theme::default()),
+        };
+        std::fs::write(&path, output)?;
+        println!("Exported diff to {}", path.display());
+        return Ok(());
+    }
 
-    if app.diff().is_none() {
-        // Exit programme gracefully when no diff is found
-        println!("No diff found, exiting");
+    if args.print() {
+        let output = view::print::render_to_string(&app, args.width(), options.color());
+        write_stdout(&output)?;
         return Ok(());
     }
 
     terminal::install_panic_hook();
     let mut terminal = terminal::init_terminal()?;
+    let mut graphics = GraphicsLayer::new(options.image_protocol());
 
     let mut previous_log_length = app.console().len();
     // Will exit when RunningState is 'Done'
@@ -47,6 +96,9 @@ fn main() -> Result<()> {
 
         // Render ui
         terminal.draw(|rect| view::view(&mut app, rect))?;
+        if graphics.draw(&app, terminal.size()?)? {
+            terminal.clear()?;
+        }
 
         let mut current_msg = app.handle_event()?;
 
@@ -59,3 +111,54 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+fn run_command(command: Command) -> Result<()> {
+    match command {
+        Command::Config {
+            action: ConfigAction::Check,
+        } => {
+            let config = AppConfig::new(None, Default::default())?;
+            let layers: Vec<String> = config.layers().iter().map(ToString::to_string).collect();
+            println!(
+                "Config is valid ({} bindings) from:\n  {}",
+                config.keymap().len(),
+                layers.join("\n  ")
+            );
+        }
+        Command::Config {
+            action: ConfigAction::Show { effective: true },
+        } => {
+            let config = AppConfig::new(None, Default::default())?;
+            print!("{}", config.show_effective());
+        }
+        Command::Config {
+            action: ConfigAction::Show { effective: false },
+        } => {
+            let user = get_config_path()?;
+            let project = find_project_config(&std::env::current_dir()?);
+            let not_found = |found: bool| if found { "" } else { " (not found)" };
+            println!("built-in");
+            println!("user config {}{}", user.display(), not_found(user.exists()));
+            println!(
+                "project config {}{}",
+                project
+                    .as_deref()
+                    .unwrap_or(Path::new(PROJECT_CONFIG))
+                    .display(),
+                not_found(project.is_some())
+            );
+            println!("environment DIFF_TOOL_*");
+            println!("command line");
+        }
+    }
+    Ok(())
+}
+
+/// Writes non-interactive output to stdout
+fn write_stdout(output: &str) -> Result<()> {
+    match std::io::stdout().write_all(output.as_bytes()) {
+        // The reader (e.g. `head` or `less`) went away, nothing left to do
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
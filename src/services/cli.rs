@@ -1,29 +1,298 @@
-use clap::Parser;
-use std::path::{Path, PathBuf};
+use anyhow::{bail, Result};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
 
-use crate::services::config::{get_config_dir, get_data_dir};
+use crate::{
+    services::{
+        config::{get_config_dir, get_data_dir},
+        source::DiffSource,
+    },
+    view::image::ImageProtocol,
+};
 
 #[derive(Parser, Debug)]
 #[command(author = "Ddraigan", version = version(), about = "A side by side git diff view")]
+#[command(group(ArgGroup::new("input").args(["paths", "patch"])))]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     #[clap(short = 'C', long)]
     /// Giving a full path allows diff-tool to diff outside of the git repo
     change_dir: bool,
-    /// File to diff with
-    path: PathBuf,
+    /// File to diff with, or `-` to read a unified diff from stdin (e.g. `git diff | diff-tool -`).
+    /// A directory, or no path at all for the current one, diffs every changed file under it.
+    ///
+    /// Two paths (`$LOCAL $REMOTE`) compare the files directly, as `git difftool` does, or every
+    /// file of two directories. The seven arguments passed by git to a `GIT_EXTERNAL_DIFF` program,
+    /// or nine for a rename or copy, are also accepted.
+    #[clap(value_name = "PATH", num_args = 0..=9)]
+    paths: Vec<PathBuf>,
+    #[clap(long, value_name = "FILE")]
+    /// Read a unified diff or patch from a file instead of running git diff
+    patch: Option<PathBuf>,
+    #[clap(long, value_name = "GLOB")]
+    /// Leave out files and directories matching the glob when comparing two directories, e.g.
+    /// `--ignore target --ignore '*.lock'`. Matched against the relative path and the name.
+    ignore: Vec<String>,
+    #[clap(long, value_name = "PATH")]
+    /// Path shown in the pane titles when comparing two files, e.g. `$MERGED` from git difftool
+    label: Option<String>,
+    #[clap(long)]
+    /// Print the side by side diff to stdout instead of opening the viewer
+    print: bool,
+    #[clap(long, value_name = "WHEN")]
+    /// Whether `--print` output uses ANSI colours [default: auto]
+    color: Option<ColorChoice>,
+    #[clap(long, value_name = "COLUMNS", env = "COLUMNS")]
+    /// Width of `--print` output, defaults to the terminal width
+    width: Option<u16>,
+    #[clap(long, num_args = 2, value_names = ["FORMAT", "FILE"])]
+    /// Write the diff to a file instead of opening the viewer, e.g. `--export html out.html`
+    export: Vec<String>,
+    #[clap(long, value_name = "FORMAT")]
+    /// Write the parsed diff model to stdout in a machine-readable format instead of opening the
+    /// viewer
+    format: Option<OutputFormat>,
+    #[clap(long, value_name = "LABEL")]
+    /// Text encoding of the files, e.g. `latin1` or `utf-16le`, detected when not given
+    encoding: Option<String>,
+    #[clap(short = 'w', long)]
+    /// Ignore whitespace when comparing lines
+    ignore_all_space: bool,
+    #[clap(short = 'b', long)]
+    /// Ignore changes in the amount of whitespace
+    ignore_space_change: bool,
+    #[clap(long)]
+    /// Ignore changes whose lines are all blank
+    ignore_blank_lines: bool,
+    #[clap(long)]
+    /// Ignore carriage returns at the end of lines, so CRLF and LF line endings match
+    ignore_eol: bool,
+    #[clap(long, value_name = "PROTOCOL")]
+    /// How image files are previewed, `i` switches between the preview and the diff [default:
+    /// auto]
+    image_protocol: Option<ImageProtocol>,
+    #[clap(long, value_name = "LEVEL")]
+    /// Log level, `trace` to `error` or `off`, or a filter such as `diff_tool=debug,globset=warn`
+    log_level: Option<String>,
+    #[clap(long, value_name = "FILE", conflicts_with = "no_log_file")]
+    /// Write the log to this file instead of `diff-tool.log` in the data directory
+    log_file: Option<PathBuf>,
+    #[clap(long)]
+    /// Don't write a log file, the console still shows the logs
+    no_log_file: bool,
     // #[clap(short, long, default_value_t = 250)]
     // tick_rate: u64,
     // TODO: Implement tick rate arg
 }
 
+/// Tasks run instead of showing a diff. A file named like one of them is diffed as `./config`.
+#[derive(Subcommand, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Work with the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigAction {
+    /// Check the config files for invalid keys, unknown actions and keys bound twice
+    Check,
+    /// List the config layers that apply here, lowest precedence first
+    Show {
+        /// Print the merged config instead, with the layer that set each value
+        #[arg(long)]
+        effective: bool,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Colour when stdout is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// File formats the diff can be exported to
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A self-contained HTML page
+    Html,
+}
+
+/// Machine-readable formats for `--format`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The versioned JSON document described in `services::json`
+    Json,
+}
+
+impl ColorChoice {
+    /// Whether to colour output, resolving `Auto` from stdout and `NO_COLOR`
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+        }
+    }
+}
+
 impl Args {
-    pub fn path(&self) -> &Path {
-        &self.path
+    pub fn command(&self) -> Option<Command> {
+        self.command
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.paths.first().map(PathBuf::as_path)
     }
 
     pub fn change_dir(&self) -> bool {
         self.change_dir
     }
+
+    pub fn print(&self) -> bool {
+        self.print
+    }
+
+    /// Width of `--print` output, from `--width`/`$COLUMNS`, then the terminal, then 120 columns
+    pub fn width(&self) -> u16 {
+        self.width
+            .or_else(|| crossterm::terminal::size().ok().map(|(columns, _)| columns))
+            .unwrap_or(120)
+    }
+
+    pub fn format(&self) -> Option<OutputFormat> {
+        self.format
+    }
+
+    /// The options given as flags, the top layer of the config
+    pub fn config_overrides(&self) -> toml::Table {
+        let mut overrides = toml::Table::new();
+        let mut set = |name: &str, value: toml::Value| {
+            overrides.insert(name.to_string(), value);
+        };
+
+        if let Some(color) = self.color.and_then(|color| color.to_possible_value()) {
+            set("color", color.get_name().into());
+        }
+        if let Some(protocol) = self.image_protocol.and_then(|p| p.to_possible_value()) {
+            set("image_protocol", protocol.get_name().into());
+        }
+        if let Some(encoding) = &self.encoding {
+            set("encoding", encoding.as_str().into());
+        }
+        if let Some(level) = &self.log_level {
+            set("log_level", level.as_str().into());
+        }
+        if let Some(path) = &self.log_file {
+            set("log_file", path.to_string_lossy().as_ref().into());
+        }
+        if self.no_log_file {
+            set("log_to_file", false.into());
+        }
+        let flags = [
+            ("ignore_all_space", self.ignore_all_space),
+            ("ignore_space_change", self.ignore_space_change),
+            ("ignore_blank_lines", self.ignore_blank_lines),
+            ("ignore_eol", self.ignore_eol),
+        ];
+        for (name, enabled) in flags {
+            if enabled {
+                set(name, true.into());
+            }
+        }
+        overrides
+    }
+
+    /// The format and destination passed to `--export`, if any
+    pub fn export(&self) -> Result<Option<(ExportFormat, PathBuf)>> {
+        match self.export.as_slice() {
+            [format, path] => {
+                let format = ExportFormat::from_str(format, true)
+                    .map_err(|_| anyhow::anyhow!("Unknown export format '{}'", format))?;
+                Ok(Some((format, PathBuf::from(path))))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Works out where the diff should be read from
+    pub fn source(&self) -> Result<DiffSource> {
+        if let Some(patch) = &self.patch {
+            return Ok(DiffSource::Patch(Some(patch.clone())));
+        }
+
+        let source = match self.paths.as_slice() {
+            [] => DiffSource::Git {
+                path: PathBuf::from("."),
+                change_dir: self.change_dir,
+            },
+            [path] if path == Path::new("-") => DiffSource::Patch(None),
+            [path] => DiffSource::Git {
+                path: path.clone(),
+                change_dir: self.change_dir,
+            },
+            [old, new] if old.is_dir() && new.is_dir() => DiffSource::Directories {
+                old: old.clone(),
+                new: new.clone(),
+                ignore: self.ignore.clone(),
+            },
+            // git difftool: $LOCAL $REMOTE
+            [old, new] => DiffSource::Files {
+                old: old.clone(),
+                new: new.clone(),
+                label: self.label.clone(),
+                new_label: None,
+            },
+            // GIT_EXTERNAL_DIFF: path old-file old-hex old-mode new-file new-hex new-mode
+            [path, old, _old_hex, _old_mode, new, _new_hex, _new_mode] => DiffSource::Files {
+                old: old.clone(),
+                new: new.clone(),
+                label: Some(
+                    self.label
+                        .clone()
+                        .unwrap_or_else(|| path.display().to_string()),
+                ),
+                new_label: None,
+            },
+            // GIT_EXTERNAL_DIFF for a rename or copy: ... new-mode new-path xfrm-msg
+            [path, old, _old_hex, _old_mode, new, _new_hex, _new_mode, new_path, _xfrm_msg] => {
+                DiffSource::Files {
+                    old: old.clone(),
+                    new: new.clone(),
+                    label: Some(
+                        self.label
+                            .clone()
+                            .unwrap_or_else(|| path.display().to_string()),
+                    ),
+                    new_label: Some(
+                        self.label
+                            .clone()
+                            .unwrap_or_else(|| new_path.display().to_string()),
+                    ),
+                }
+            }
+            paths => bail!(
+                "Expected 1 path, 2 paths (difftool) or 7 or 9 arguments (GIT_EXTERNAL_DIFF), got {}",
+                paths.len()
+            ),
+        };
+
+        Ok(source)
+    }
 }
 
 pub fn version() -> clap::builder::Str {
@@ -45,3 +314,38 @@ pub fn version() -> clap::builder::Str {
     )
     .into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_external_diff_rename() {
+        let args = Args::parse_from([
+            "diff-tool",
+            "old.rs",
+            "/tmp/old.rs",
+            "1111111",
+            "100644",
+            "/tmp/new.rs",
+            "2222222",
+            "100644",
+            "new.rs",
+            "similarity index 90%\nrename from old.rs\nrename to new.rs\n",
+        ]);
+
+        let DiffSource::Files {
+            old,
+            new,
+            label,
+            new_label,
+        } = args.source().unwrap()
+        else {
+            panic!("expected two files to compare");
+        };
+        assert_eq!(old, Path::new("/tmp/old.rs"));
+        assert_eq!(new, Path::new("/tmp/new.rs"));
+        assert_eq!(label.as_deref(), Some("old.rs"));
+        assert_eq!(new_label.as_deref(), Some("new.rs"));
+    }
+}
@@ -1,18 +1,157 @@
-use crate::update::message::Message;
+use crate::{
+    diff::WhitespaceOptions,
+    services::{cli::ColorChoice, encoding, source::DiffOptions},
+    update::{
+        keys::{Key, KeyParseError},
+        message::Message,
+    },
+    view::image::ImageProtocol,
+};
 use anyhow::{bail, Result};
 use directories::ProjectDirs;
-use serde::Deserialize;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use serde::{
+    de::{value::StrDeserializer, IntoDeserializer},
+    Deserialize, Serialize,
+};
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+    io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
 
-#[derive(Debug, Default, Deserialize)]
+/// Name of the project config file, looked for from the current directory up to the repo root
+pub const PROJECT_CONFIG: &str = ".diff-tool.toml";
+
+/// Name of the log file in the data directory
+pub const LOG_FILE: &str = concat!(env!("CARGO_PKG_NAME"), ".log");
+
+/// Prefix of the environment variables that set options, e.g. `DIFF_TOOL_IGNORE_ALL_SPACE=true`
+const ENV_PREFIX: &str = "DIFF_TOOL_";
+
+/// The built-in configuration, shipped as `config.toml` in the repository
+const DEFAULT_CONFIG: &str = include_str!("../../config.toml");
+
+/// The configuration merged from every layer, see [`AppConfig::new`]
+#[derive(Debug, Default)]
 pub struct AppConfig {
     keymap: KeyMap,
+    options: Options,
+    /// The layers merged, lowest precedence first
+    layers: Vec<ConfigSource>,
+    /// Layer that last set each binding, by its keys
+    binding_sources: HashMap<String, ConfigSource>,
+    /// Layer that last set each option, by name
+    option_sources: HashMap<String, ConfigSource>,
     // TODO: Colour schemes
     // colour_scheme: HashMap<String, String>,
 }
 
-#[derive(Clone, Debug, Default, Deserialize)]
+/// Settings under `[options]` that can also be given as `DIFF_TOOL_*` environment variables,
+/// and mostly as command line flags
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Options {
+    color: ColorChoice,
+    image_protocol: ImageProtocol,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<String>,
+    ignore_all_space: bool,
+    ignore_space_change: bool,
+    ignore_blank_lines: bool,
+    ignore_eol: bool,
+    /// Whether the footer lists the key bindings next to the console, `?` shows them all
+    footer_help: bool,
+    /// `trace` to `error` or `off` for diff-tool's own logs, or a full filter such as
+    /// `diff_tool=debug,globset=warn`. Falls back to `RUST_LOG`, then `info`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    log_level: Option<String>,
+    /// Where the log file is written, `diff-tool.log` in the data directory by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    log_file: Option<PathBuf>,
+    log_to_file: bool,
+    /// Size in bytes the log file grows to before it's rotated, `0` never rotates it
+    log_max_size: u64,
+    /// How many rotated log files are kept, as `diff-tool.log.1` (newest) and so on
+    log_keep: usize,
+}
+
+/// Names of the [`Options`], for picking out their environment variables
+const OPTION_NAMES: [&str; 13] = [
+    "color",
+    "image_protocol",
+    "encoding",
+    "ignore_all_space",
+    "ignore_space_change",
+    "ignore_blank_lines",
+    "ignore_eol",
+    "footer_help",
+    "log_level",
+    "log_file",
+    "log_to_file",
+    "log_max_size",
+    "log_keep",
+];
+
+/// Options a project config can't set, as a repository could use them to write over the user's
+/// files. Only the user's config, the environment and the command line set them.
+const USER_ONLY_OPTIONS: [&str; 5] = [
+    "log_level",
+    "log_file",
+    "log_to_file",
+    "log_max_size",
+    "log_keep",
+];
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            color: Default::default(),
+            image_protocol: Default::default(),
+            encoding: None,
+            ignore_all_space: false,
+            ignore_space_change: false,
+            ignore_blank_lines: false,
+            ignore_eol: false,
+            footer_help: true,
+            log_level: None,
+            log_file: None,
+            log_to_file: true,
+            log_max_size: 1024 * 1024,
+            log_keep: 3,
+        }
+    }
+}
+
+/// Where a config value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    BuiltIn,
+    /// `config.toml` in the config directory
+    User(PathBuf),
+    /// A [`PROJECT_CONFIG`] file
+    Project(PathBuf),
+    /// A `DIFF_TOOL_*` environment variable
+    Env(String),
+    CommandLine,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::BuiltIn => write!(f, "built-in"),
+            ConfigSource::User(path) => write!(f, "user config {}", path.display()),
+            ConfigSource::Project(path) => write!(f, "project config {}", path.display()),
+            ConfigSource::Env(var) => write!(f, "environment {}", var),
+            ConfigSource::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+/// Bindings from keys to messages, by the keys as displayed by [`Key`]. A sequence of keys is
+/// written as the keys separated by spaces, e.g. `"g g" = "FirstRow"`.
+#[derive(Clone, Debug, Default)]
 pub struct KeyMap(HashMap<String, Message>);
 
 impl std::ops::Deref for KeyMap {
@@ -23,52 +162,480 @@ impl std::ops::Deref for KeyMap {
     }
 }
 
+/// What a sequence of keys typed so far is bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    /// Bound to a message and not the start of any longer sequence
+    Message(Message),
+    /// The start of longer sequences, and bound to a message itself when `Some`
+    Prefix(Option<Message>),
+    Unbound,
+}
+
+/// A problem found in a config file
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("{}: {error}", .path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        error: io::Error,
+    },
+    #[error("{}: {error}", .path.display())]
+    Syntax {
+        path: PathBuf,
+        #[source]
+        error: toml::de::Error,
+    },
+    #[error("{}:{line}: \"{binding}\" isn't a valid key: {error}", .path.display())]
+    InvalidKey {
+        path: PathBuf,
+        line: usize,
+        binding: String,
+        #[source]
+        error: KeyParseError,
+    },
+    #[error("{}:{line}: unknown action \"{action}\" for \"{binding}\"", .path.display())]
+    UnknownAction {
+        path: PathBuf,
+        line: usize,
+        binding: String,
+        action: String,
+    },
+    #[error("{}:{line}: \"{binding}\" is bound more than once", .path.display())]
+    Duplicate {
+        path: PathBuf,
+        line: usize,
+        binding: String,
+    },
+    #[error("{origin}: {option} can only be set in the user config, environment or command line")]
+    UserOnly {
+        origin: ConfigSource,
+        option: String,
+    },
+    #[error("{origin}: invalid options: {error}")]
+    Options {
+        origin: ConfigSource,
+        #[source]
+        error: toml::de::Error,
+    },
+}
+
+/// Every problem found in a config file, one per line
+#[derive(Debug, Error)]
+pub struct ConfigErrors(Vec<ConfigError>);
+
+impl ConfigErrors {
+    pub fn errors(&self) -> &[ConfigError] {
+        &self.0
+    }
+}
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        write!(f, "{}", errors.join("\n"))
+    }
+}
+
+/// The tables of a config file as written, before the keys and actions are checked
+#[derive(Deserialize)]
+struct RawConfig {
+    #[serde(default)]
+    keymap: HashMap<String, toml::Spanned<String>>,
+    #[serde(default)]
+    options: toml::Table,
+}
+
+impl KeyMap {
+    /// Looks up a sequence of keys, each as displayed by [`Key`]
+    pub fn binding(&self, keys: &[String]) -> Binding {
+        let sequence = keys.join(" ");
+        let message = self.0.get(&sequence).copied();
+        let prefix = format!("{} ", sequence);
+        let is_prefix = self.0.keys().any(|keys| keys.starts_with(&prefix));
+
+        match (message, is_prefix) {
+            (_, true) => Binding::Prefix(message),
+            (Some(message), false) => Binding::Message(message),
+            (None, false) => Binding::Unbound,
+        }
+    }
+
+    /// Checks each binding of a `[keymap]` table, reporting every problem with the line it's on
+    fn parse(
+        bindings: HashMap<String, toml::Spanned<String>>,
+        path: &Path,
+        text: &str,
+    ) -> Result<Self, ConfigErrors> {
+        let mut bindings: Vec<_> = bindings.into_iter().collect();
+        bindings.sort_by_key(|(_, action)| action.span().start);
+
+        let mut keymap = HashMap::new();
+        let mut errors = Vec::new();
+        for (binding, action) in bindings {
+            let path = path.to_path_buf();
+            let line = text[..action.span().start].matches('\n').count() + 1;
+
+            let keys = binding
+                .split_whitespace()
+                .map(|key| key.parse::<Key>().map(|key| key.to_string()))
+                .collect::<Result<Vec<_>, _>>();
+            let keys = match keys {
+                Ok(keys) if keys.is_empty() => Err(KeyParseError::Empty),
+                keys => keys,
+            };
+            let deserializer: StrDeserializer<serde::de::value::Error> =
+                action.get_ref().as_str().into_deserializer();
+
+            match (keys, Message::deserialize(deserializer)) {
+                (Err(error), _) => errors.push(ConfigError::InvalidKey {
+                    path,
+                    line,
+                    binding,
+                    error,
+                }),
+                (_, Err(_)) => errors.push(ConfigError::UnknownAction {
+                    path,
+                    line,
+                    binding,
+                    action: action.into_inner(),
+                }),
+                (Ok(keys), Ok(message)) => {
+                    if keymap.insert(keys.join(" "), message).is_some() {
+                        errors.push(ConfigError::Duplicate {
+                            path,
+                            line,
+                            binding,
+                        });
+                    }
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Self(keymap))
+        } else {
+            Err(ConfigErrors(errors))
+        }
+    }
+}
+
 impl AppConfig {
-    pub fn new() -> Result<Self> {
-        let config_dir = get_config_dir()?;
+    /// Merges the built-in config, the user's `config.toml`, the project's [`PROJECT_CONFIG`],
+    /// `DIFF_TOOL_*` environment variables and then `overrides` from the command line, each
+    /// replacing the bindings and options set before it. Fails with every problem found rather
+    /// than ignoring them.
+    ///
+    /// The project config is looked for from `project_dir`, the directory of the files being
+    /// compared, or from the current directory without one.
+    pub fn new(project_dir: Option<&Path>, overrides: toml::Table) -> Result<Self> {
+        Self::load(
+            &get_config_path()?,
+            project_dir,
+            std::env::vars(),
+            overrides,
+        )
+    }
 
-        // Specify the path to the config file
-        let config_path = config_dir.join("config.toml");
+    /// [`AppConfig::new`] with the user's config read from `user_path` and the `DIFF_TOOL_*`
+    /// variables picked out of `env`
+    fn load(
+        user_path: &Path,
+        project_dir: Option<&Path>,
+        env: impl IntoIterator<Item = (String, String)>,
+        overrides: toml::Table,
+    ) -> Result<Self> {
+        let mut config = Self::defaults()?;
+        let mut errors = Vec::new();
 
-        let config = config::Config::builder()
-            .add_source(config::File::from(config_path.clone()))
-            .build()
-            .unwrap_or(Self::default_config()?);
+        if user_path.exists() {
+            config.merge_file(
+                user_path,
+                ConfigSource::User(user_path.to_path_buf()),
+                &mut errors,
+            );
+        }
+        let current_dir = std::env::current_dir()?;
+        let project_dir = match project_dir {
+            Some(dir) => current_dir.join(dir),
+            None => current_dir,
+        };
+        if let Some(project_path) = find_project_config(&project_dir) {
+            config.merge_file(
+                &project_path,
+                ConfigSource::Project(project_path.clone()),
+                &mut errors,
+            );
+        }
+        for (var, value) in env {
+            let Some(name) = var.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let name = name.to_lowercase();
+            if OPTION_NAMES.contains(&name.as_str()) {
+                let value = config.env_value(&name, &value);
+                let options = toml::Table::from_iter([(name, value)]);
+                config.merge_options(options, ConfigSource::Env(var), &mut errors);
+            }
+        }
+        config.merge_options(overrides, ConfigSource::CommandLine, &mut errors);
 
-        let config = config.try_deserialize()?;
+        if !errors.is_empty() {
+            return Err(ConfigErrors(errors).into());
+        }
+        Ok(config)
+    }
 
+    /// The built-in configuration, without the user's config file
+    pub fn defaults() -> Result<Self> {
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+        config.merge_text(
+            DEFAULT_CONFIG,
+            Path::new("<built-in config>"),
+            ConfigSource::BuiltIn,
+            &mut errors,
+        );
+        if !errors.is_empty() {
+            return Err(ConfigErrors(errors).into());
+        }
         Ok(config)
     }
 
+    fn merge_file(&mut self, path: &Path, source: ConfigSource, errors: &mut Vec<ConfigError>) {
+        match std::fs::read_to_string(path) {
+            Ok(text) => self.merge_text(&text, path, source, errors),
+            Err(error) => errors.push(ConfigError::Read {
+                path: path.to_path_buf(),
+                error,
+            }),
+        }
+    }
+
+    fn merge_text(
+        &mut self,
+        text: &str,
+        path: &Path,
+        source: ConfigSource,
+        errors: &mut Vec<ConfigError>,
+    ) {
+        let raw: RawConfig = match toml::from_str(text) {
+            Ok(raw) => raw,
+            Err(error) => {
+                errors.push(ConfigError::Syntax {
+                    path: path.to_path_buf(),
+                    error,
+                });
+                return;
+            }
+        };
+
+        self.layers.push(source.clone());
+        match KeyMap::parse(raw.keymap, path, text) {
+            Ok(keymap) => {
+                for (keys, message) in keymap.0 {
+                    self.binding_sources.insert(keys.clone(), source.clone());
+                    self.keymap.0.insert(keys, message);
+                }
+            }
+            Err(ConfigErrors(keymap_errors)) => errors.extend(keymap_errors),
+        }
+        self.merge_options(raw.options, source, errors);
+    }
+
+    /// Sets the options named in `options` over the current ones
+    fn merge_options(
+        &mut self,
+        mut options: toml::Table,
+        source: ConfigSource,
+        errors: &mut Vec<ConfigError>,
+    ) {
+        if matches!(source, ConfigSource::Project(_)) {
+            for option in USER_ONLY_OPTIONS {
+                if options.remove(option).is_some() {
+                    errors.push(ConfigError::UserOnly {
+                        origin: source.clone(),
+                        option: option.to_string(),
+                    });
+                }
+            }
+        }
+        if options.is_empty() {
+            return;
+        }
+        let mut merged = self.options.to_table();
+        merged.extend(options.clone());
+
+        match Options::deserialize(toml::Value::Table(merged)) {
+            Ok(merged) => {
+                self.options = merged;
+                for name in options.keys() {
+                    self.option_sources.insert(name.clone(), source.clone());
+                }
+                if self.layers.last() != Some(&source) {
+                    self.layers.push(source);
+                }
+            }
+            Err(error) => errors.push(ConfigError::Options {
+                origin: source,
+                error,
+            }),
+        }
+    }
+
+    /// Reads an environment variable's value as a TOML value, e.g. `true` or `3`, or as a string
+    /// when the option doesn't take that type, such as `DIFF_TOOL_ENCODING=866`
+    fn env_value(&self, name: &str, value: &str) -> toml::Value {
+        let string = toml::Value::String(value.to_string());
+        let typed = if let Ok(value) = value.parse::<bool>() {
+            toml::Value::Boolean(value)
+        } else if let Ok(value) = value.parse::<i64>() {
+            toml::Value::Integer(value)
+        } else {
+            return string;
+        };
+        let mut options = self.options.to_table();
+        options.insert(name.to_string(), typed.clone());
+        match Options::deserialize(toml::Value::Table(options)) {
+            Ok(_) => typed,
+            Err(_) => string,
+        }
+    }
+
     pub fn keymap(&self) -> &KeyMap {
         &self.keymap
     }
 
-    fn default_config() -> Result<config::Config> {
-        let default_keymap = r#"[keymap]
-"esc" = "Quit"
-"q" = "Quit"
-"ctrl+c" = "Quit"
-"ctrl+d" = "Quit"
-"Shift+g" = "LastRow"
-"g" = "FirstRow"
-"j" = "NextRow"
-"k" = "PrevRow"
-
-[colour_scheme]
-"fg" = "white"
-"bg" = "transparent"
-"text" = "white""#;
-
-        let config = config::Config::builder()
-            .add_source(config::File::from_str(
-                default_keymap,
-                config::FileFormat::Toml,
-            ))
-            .build()?;
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
 
-        Ok(config)
+    /// The layers that were merged, lowest precedence first
+    pub fn layers(&self) -> &[ConfigSource] {
+        &self.layers
+    }
+
+    /// The merged config as TOML, each value commented with the layer that set it
+    pub fn show_effective(&self) -> String {
+        let mut output = String::from("[keymap]\n");
+        let mut bindings: Vec<_> = self.keymap.iter().collect();
+        bindings.sort();
+        for (keys, message) in bindings {
+            let _ = writeln!(
+                output,
+                "{} = {}  # {}",
+                toml_string(keys),
+                toml_string(&format!("{:?}", message)),
+                source_of(&self.binding_sources, keys)
+            );
+        }
+
+        output.push_str("\n[options]\n");
+        for (name, value) in &self.options.to_table() {
+            let _ = writeln!(
+                output,
+                "{} = {}  # {}",
+                name,
+                value,
+                source_of(&self.option_sources, name)
+            );
+        }
+        output
+    }
+}
+
+fn source_of(sources: &HashMap<String, ConfigSource>, key: &str) -> String {
+    sources
+        .get(key)
+        .map_or_else(|| "default".to_string(), ToString::to_string)
+}
+
+impl Options {
+    fn to_table(&self) -> toml::Table {
+        match toml::Value::try_from(self) {
+            Ok(toml::Value::Table(table)) => table,
+            _ => toml::Table::new(),
+        }
+    }
+
+    /// Whether `--print` output should be coloured
+    pub fn color(&self) -> bool {
+        self.color.enabled()
+    }
+
+    pub fn image_protocol(&self) -> ImageProtocol {
+        self.image_protocol
+    }
+
+    pub fn footer_help(&self) -> bool {
+        self.footer_help
+    }
+
+    pub fn log_level(&self) -> Option<&str> {
+        self.log_level.as_deref()
+    }
+
+    /// Where to write the log file, `None` when logging to a file is turned off
+    pub fn log_file(&self) -> Result<Option<PathBuf>> {
+        if !self.log_to_file {
+            return Ok(None);
+        }
+        match &self.log_file {
+            Some(path) => Ok(Some(path.clone())),
+            None => Ok(Some(get_data_dir()?.join(LOG_FILE))),
+        }
+    }
+
+    pub fn log_max_size(&self) -> u64 {
+        self.log_max_size
+    }
+
+    pub fn log_keep(&self) -> usize {
+        self.log_keep
     }
+
+    /// Options for loading the diff, fails on an unknown encoding
+    pub fn diff_options(&self) -> Result<DiffOptions> {
+        let encoding = self
+            .encoding
+            .as_deref()
+            .map(encoding::parse_label)
+            .transpose()?;
+        let whitespace = WhitespaceOptions::default()
+            .ignore_all_space(self.ignore_all_space)
+            .ignore_space_change(self.ignore_space_change)
+            .ignore_blank_lines(self.ignore_blank_lines)
+            .ignore_eol(self.ignore_eol);
+        Ok(DiffOptions::default()
+            .with_encoding(encoding)
+            .with_whitespace(whitespace))
+    }
+}
+
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+/// The nearest [`PROJECT_CONFIG`] from `dir` up to the root of the repository it's in, or just
+/// in `dir` outside of a repository
+pub fn find_project_config(dir: &Path) -> Option<PathBuf> {
+    let in_repo = dir.ancestors().any(|dir| dir.join(".git").exists());
+    for dir in dir.ancestors() {
+        let path = dir.join(PROJECT_CONFIG);
+        if path.is_file() {
+            return Some(path);
+        }
+        if !in_repo || dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+/// Where the user's config file is read from
+pub fn get_config_path() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("config.toml"))
 }
 
 pub fn get_data_dir() -> Result<PathBuf> {
@@ -98,7 +665,14 @@ mod tests {
     use super::*;
     use std::fs::File;
     use std::io::Write;
-    use std::path::Path;
+
+    fn parse(text: &str) -> (AppConfig, Vec<ConfigError>) {
+        let mut config = AppConfig::default();
+        let mut errors = Vec::new();
+        let path = Path::new("config.toml");
+        config.merge_text(text, path, ConfigSource::User(path.into()), &mut errors);
+        (config, errors)
+    }
 
     #[test]
     fn test_config_dir() {
@@ -111,23 +685,164 @@ mod tests {
 
     #[test]
     fn test_new() -> Result<()> {
-        // Create a mock configuration file
-        let config_path = Path::new("config.toml");
+        // Create a mock configuration file in a scratch config directory
+        let config_dir = std::env::temp_dir().join("diff-tool-test-new");
+        std::fs::create_dir_all(&config_dir)?;
+        let config_path = config_dir.join("config.toml");
         let mut file = File::create(&config_path)?;
         write!(
             file,
-            "[keymap]\n\"key1\" = \"Quit\"\n\n[colour_scheme]\n\"fg\" = \"white\""
+            "[keymap]\n\"ctrl+x\" = \"Quit\"\n\"j\" = \"PrevRow\"\n\n[options]\nignore_eol = true\ncolor = \"never\"\n\n[colour_scheme]\n\"fg\" = \"white\""
         )?;
 
-        // Call the function
-        let config = AppConfig::new()?;
-
-        // Check the result
-        assert_eq!(config.keymap.0.get("key1"), Some(&Message::Quit));
+        // Call the function, with `DIFF_TOOL_COLOR` set and `-w` given on the command line
+        let env = [
+            ("DIFF_TOOL_COLOR".to_string(), "always".to_string()),
+            ("DIFF_TOOL_UNKNOWN".to_string(), "1".to_string()),
+            ("COLOR".to_string(), "never".to_string()),
+        ];
+        let overrides = toml::Table::from_iter([("ignore_all_space".to_string(), true.into())]);
+        let config = AppConfig::load(&config_path, Some(&config_dir), env, overrides);
 
         // Clean up
-        std::fs::remove_file(config_path)?;
+        std::fs::remove_dir_all(config_dir)?;
+
+        // Check the result: the file's bindings are added to and replace the defaults, and
+        // each option comes from the highest layer that sets it
+        let config = config?;
+        assert_eq!(config.keymap.0.get("ctrl+x"), Some(&Message::Quit));
+        assert_eq!(config.keymap.0.get("j"), Some(&Message::PrevRow));
+        assert_eq!(config.keymap.0.get("k"), Some(&Message::PrevRow));
+        assert!(config.options().ignore_eol);
+        assert!(config.options().ignore_all_space);
+        assert_eq!(config.options().color, ColorChoice::Always);
+
+        let effective = config.show_effective();
+        assert!(effective.contains("\"j\" = \"PrevRow\"  # user config"));
+        assert!(effective.contains("\"k\" = \"PrevRow\"  # built-in"));
+        assert!(effective.contains("color = \"always\"  # environment DIFF_TOOL_COLOR"));
+        assert!(effective.contains("ignore_all_space = true  # command line"));
 
         Ok(())
     }
+
+    #[test]
+    fn test_find_project_config() -> Result<()> {
+        let repo = std::env::temp_dir().join("diff-tool-test-project-config");
+        let nested = repo.join("src").join("app");
+        std::fs::create_dir_all(&nested)?;
+        std::fs::create_dir_all(repo.join(".git"))?;
+        std::fs::write(repo.join(PROJECT_CONFIG), "[options]\nignore_eol = true\n")?;
+
+        let found = find_project_config(&nested);
+        std::fs::remove_dir_all(&repo)?;
+
+        assert_eq!(found, Some(repo.join(PROJECT_CONFIG)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_config_from_compared_paths() -> Result<()> {
+        let repo = std::env::temp_dir().join("diff-tool-test-project-dir");
+        let nested = repo.join("src");
+        std::fs::create_dir_all(&nested)?;
+        std::fs::create_dir_all(repo.join(".git"))?;
+        std::fs::write(repo.join(PROJECT_CONFIG), "[options]\nignore_eol = true\n")?;
+
+        let missing = repo.join("no-user-config.toml");
+        let config = AppConfig::load(&missing, Some(&nested), [], Default::default());
+        std::fs::remove_dir_all(&repo)?;
+
+        let config = config?;
+        assert!(config.options().ignore_eol);
+        assert!(config
+            .layers()
+            .contains(&ConfigSource::Project(repo.join(PROJECT_CONFIG))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_env_value() {
+        let config = AppConfig::default();
+        assert_eq!(
+            config.env_value("encoding", "866"),
+            toml::Value::String("866".to_string())
+        );
+        assert_eq!(config.env_value("log_keep", "5"), toml::Value::Integer(5));
+        assert_eq!(
+            config.env_value("ignore_eol", "true"),
+            toml::Value::Boolean(true)
+        );
+        assert_eq!(
+            config.env_value("color", "always"),
+            toml::Value::String("always".to_string())
+        );
+    }
+
+    #[test]
+    fn test_project_config_cannot_set_log_options() {
+        let mut config = AppConfig::default();
+        let mut errors = Vec::new();
+        let path = Path::new(PROJECT_CONFIG);
+        let text = "[options]\nlog_file = \"/home/user/.bashrc\"\nignore_eol = true\n";
+        config.merge_text(text, path, ConfigSource::Project(path.into()), &mut errors);
+
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            ["project config .diff-tool.toml: log_file can only be set in the user config, environment or command line"]
+        );
+        assert_eq!(config.options().log_file, None);
+        assert!(config.options().ignore_eol);
+    }
+
+    #[test]
+    fn test_config_errors() {
+        let text = "[keymap]\n\"j\" = \"NextRow\"\n\"hyper+x\" = \"Quit\"\n\"k\" = \"Jump\"\n\"J\" = \"Quit\"\n\"shift+j\" = \"Quit\"\n";
+        let (_, errors) = parse(text);
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            errors,
+            [
+                "config.toml:3: \"hyper+x\" isn't a valid key: unknown modifier 'hyper', expected ctrl, alt or shift",
+                "config.toml:4: unknown action \"Jump\" for \"k\"",
+                "config.toml:6: \"shift+j\" is bound more than once",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_config() {
+        let defaults = AppConfig::defaults().unwrap();
+        assert_eq!(defaults.keymap().get("shift+g"), Some(&Message::LastRow));
+        assert_eq!(defaults.keymap().get("Esc"), Some(&Message::Quit));
+
+        // Every binding and option of the shipped config is valid
+        let (_, errors) = parse(DEFAULT_CONFIG);
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(errors, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_binding() {
+        let keymap = KeyMap(HashMap::from([
+            ("g g".to_string(), Message::FirstRow),
+            ("]".to_string(), Message::NextConflict),
+            ("] f".to_string(), Message::NextFile),
+            ("j".to_string(), Message::NextRow),
+        ]));
+        let keys = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(keymap.binding(&keys(&["g"])), Binding::Prefix(None));
+        assert_eq!(
+            keymap.binding(&keys(&["g", "g"])),
+            Binding::Message(Message::FirstRow)
+        );
+        assert_eq!(
+            keymap.binding(&keys(&["]"])),
+            Binding::Prefix(Some(Message::NextConflict))
+        );
+        assert_eq!(keymap.binding(&keys(&["g", "j"])), Binding::Unbound);
+    }
 }
@@ -0,0 +1,504 @@
+use anyhow::{bail, Result};
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::diff::{DiffKind, DiffLine};
+
+const OURS_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+/// How a single conflict region should be resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    /// Ours followed by theirs
+    Both,
+}
+
+/// A single `<<<<<<<` ... `>>>>>>>` region of a conflicted file
+#[derive(Debug, Clone, Default)]
+pub struct Conflict {
+    ours_label: String,
+    theirs_label: String,
+    ours: Vec<String>,
+    /// Only present when the file was written with `merge.conflictStyle = diff3` (or `zdiff3`)
+    base: Option<Vec<String>>,
+    base_label: String,
+    theirs: Vec<String>,
+    resolution: Option<Resolution>,
+}
+
+impl Conflict {
+    pub fn ours_label(&self) -> &str {
+        &self.ours_label
+    }
+
+    pub fn theirs_label(&self) -> &str {
+        &self.theirs_label
+    }
+
+    pub fn resolution(&self) -> Option<Resolution> {
+        self.resolution
+    }
+
+    /// The lines this conflict resolves to, or the original markers when unresolved
+    fn resolved_lines(&self) -> Vec<String> {
+        match self.resolution {
+            Some(Resolution::Ours) => self.ours.clone(),
+            Some(Resolution::Theirs) => self.theirs.clone(),
+            Some(Resolution::Both) => [self.ours.clone(), self.theirs.clone()].concat(),
+            None => {
+                let mut lines = vec![format_marker(OURS_MARKER, &self.ours_label)];
+                lines.extend(self.ours.iter().cloned());
+                if let Some(base) = &self.base {
+                    lines.push(format_marker(BASE_MARKER, &self.base_label));
+                    lines.extend(base.iter().cloned());
+                }
+                lines.push(SEPARATOR_MARKER.to_string());
+                lines.extend(self.theirs.iter().cloned());
+                lines.push(format_marker(THEIRS_MARKER, &self.theirs_label));
+                lines
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Common(Vec<String>),
+    Conflict(Conflict),
+}
+
+/// The base, ours and theirs panes of a conflicted file, aligned row by row
+#[derive(Debug, Default)]
+pub struct ConflictRows {
+    base: Vec<DiffLine>,
+    ours: Vec<DiffLine>,
+    theirs: Vec<DiffLine>,
+    /// Row range covered by each conflict, in file order
+    regions: Vec<Range<usize>>,
+}
+
+impl ConflictRows {
+    pub fn base(&self) -> &[DiffLine] {
+        &self.base
+    }
+
+    pub fn ours(&self) -> &[DiffLine] {
+        &self.ours
+    }
+
+    pub fn theirs(&self) -> &[DiffLine] {
+        &self.theirs
+    }
+
+    pub fn regions(&self) -> &[Range<usize>] {
+        &self.regions
+    }
+
+    pub fn len(&self) -> usize {
+        self.ours.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ours.is_empty()
+    }
+}
+
+/// A file containing merge conflict markers
+#[derive(Debug, Default)]
+pub struct ConflictFile {
+    path: PathBuf,
+    segments: Vec<Segment>,
+    line_ending: &'static str,
+    trailing_newline: bool,
+    rows: ConflictRows,
+}
+
+impl ConflictFile {
+    /// Parses the conflict markers in a working tree file
+    pub fn parse(path: &Path, content: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut common = Vec::new();
+        let mut current: Option<(Conflict, ConflictSide)> = None;
+        // Line number of the current conflict's opening marker
+        let mut opened = 0;
+
+        for (number, line) in content.lines().enumerate() {
+            let mut closed = false;
+            match current.as_mut() {
+                None => {
+                    if let Some(label) = marker_label(line, OURS_MARKER) {
+                        if !common.is_empty() {
+                            segments.push(Segment::Common(std::mem::take(&mut common)));
+                        }
+                        let conflict = Conflict {
+                            ours_label: label.to_string(),
+                            ..Default::default()
+                        };
+                        current = Some((conflict, ConflictSide::Ours));
+                        opened = number + 1;
+                    } else if line == SEPARATOR_MARKER
+                        || marker_label(line, THEIRS_MARKER).is_some()
+                    {
+                        bail!(
+                            "{}:{}: {} without an opening {} marker",
+                            path.display(),
+                            number + 1,
+                            line,
+                            OURS_MARKER
+                        );
+                    } else {
+                        common.push(line.to_string());
+                    }
+                }
+                Some((conflict, side)) => {
+                    if let Some(label) = marker_label(line, BASE_MARKER) {
+                        conflict.base_label = label.to_string();
+                        conflict.base = Some(Vec::new());
+                        *side = ConflictSide::Base;
+                    } else if line == SEPARATOR_MARKER {
+                        *side = ConflictSide::Theirs;
+                    } else if let Some(label) = marker_label(line, THEIRS_MARKER) {
+                        conflict.theirs_label = label.to_string();
+                        closed = true;
+                    } else {
+                        match side {
+                            ConflictSide::Ours => conflict.ours.push(line.to_string()),
+                            ConflictSide::Base => conflict
+                                .base
+                                .get_or_insert_with(Vec::new)
+                                .push(line.to_string()),
+                            ConflictSide::Theirs => conflict.theirs.push(line.to_string()),
+                        }
+                    }
+                }
+            }
+            if closed {
+                if let Some((conflict, _)) = current.take() {
+                    segments.push(Segment::Conflict(conflict));
+                }
+            }
+        }
+
+        if current.is_some() {
+            bail!(
+                "{}:{}: conflict is never closed with a {} marker",
+                path.display(),
+                opened,
+                THEIRS_MARKER
+            );
+        }
+        if !common.is_empty() {
+            segments.push(Segment::Common(common));
+        }
+
+        let line_ending = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+
+        let mut file = Self {
+            path: path.to_path_buf(),
+            segments,
+            line_ending,
+            trailing_newline: content.ends_with('\n'),
+            rows: Default::default(),
+        };
+        file.build_rows();
+        Ok(file)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn rows(&self) -> &ConflictRows {
+        &self.rows
+    }
+
+    pub fn conflicts(&self) -> impl Iterator<Item = &Conflict> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Conflict(conflict) => Some(conflict),
+            Segment::Common(_) => None,
+        })
+    }
+
+    pub fn conflict_count(&self) -> usize {
+        self.conflicts().count()
+    }
+
+    pub fn unresolved_count(&self) -> usize {
+        self.conflicts()
+            .filter(|conflict| conflict.resolution.is_none())
+            .count()
+    }
+
+    /// Index of the conflict covering the given row, if any
+    pub fn conflict_at_row(&self, row: usize) -> Option<usize> {
+        self.rows
+            .regions
+            .iter()
+            .position(|region| region.contains(&row))
+    }
+
+    /// The conflict covering the given row, otherwise the nearest one above it, otherwise the
+    /// first
+    pub fn conflict_near_row(&self, row: usize) -> Option<&Conflict> {
+        let index = self
+            .rows
+            .regions
+            .iter()
+            .rposition(|region| region.start <= row)
+            .unwrap_or_default();
+        self.conflicts().nth(index)
+    }
+
+    /// Resolves the conflict with the given index, recomputing the aligned rows
+    pub fn resolve(&mut self, index: usize, resolution: Resolution) {
+        let conflict = self
+            .segments
+            .iter_mut()
+            .filter_map(|segment| match segment {
+                Segment::Conflict(conflict) => Some(conflict),
+                Segment::Common(_) => None,
+            })
+            .nth(index);
+
+        if let Some(conflict) = conflict {
+            conflict.resolution = Some(resolution);
+            self.build_rows();
+        }
+    }
+
+    /// The file content with resolved conflicts applied, unresolved conflicts keep their markers
+    pub fn resolved_content(&self) -> String {
+        let lines = self
+            .segments
+            .iter()
+            .flat_map(|segment| match segment {
+                Segment::Common(lines) => lines.clone(),
+                Segment::Conflict(conflict) => conflict.resolved_lines(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut content = lines.join(self.line_ending);
+        if self.trailing_newline && !lines.is_empty() {
+            content.push_str(self.line_ending);
+        }
+        content
+    }
+
+    /// Writes the resolved content back over the working tree file
+    pub fn write(&self) -> Result<()> {
+        std::fs::write(&self.path, self.resolved_content())?;
+        Ok(())
+    }
+
+    fn build_rows(&mut self) {
+        let mut rows = ConflictRows::default();
+        let (mut base_line, mut ours_line, mut theirs_line) = (1, 1, 1);
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Common(lines) => {
+                    for line in lines {
+                        rows.base
+                            .push(DiffLine::new(line, DiffKind::Neutral, Some(base_line)));
+                        rows.ours
+                            .push(DiffLine::new(line, DiffKind::Neutral, Some(ours_line)));
+                        rows.theirs
+                            .push(DiffLine::new(line, DiffKind::Neutral, Some(theirs_line)));
+                        base_line += 1;
+                        ours_line += 1;
+                        theirs_line += 1;
+                    }
+                }
+                Segment::Conflict(conflict) => {
+                    let start = rows.ours.len();
+                    let base = conflict.base.as_deref().unwrap_or_default();
+                    let height = conflict
+                        .ours
+                        .len()
+                        .max(base.len())
+                        .max(conflict.theirs.len());
+
+                    let (ours_kind, theirs_kind) = match conflict.resolution {
+                        None => (DiffKind::Conflict, DiffKind::Conflict),
+                        Some(Resolution::Ours) => (DiffKind::Addition, DiffKind::Removal),
+                        Some(Resolution::Theirs) => (DiffKind::Removal, DiffKind::Addition),
+                        Some(Resolution::Both) => (DiffKind::Addition, DiffKind::Addition),
+                    };
+
+                    push_side(
+                        &mut rows.base,
+                        base,
+                        DiffKind::Conflict,
+                        &mut base_line,
+                        height,
+                    );
+                    push_side(
+                        &mut rows.ours,
+                        &conflict.ours,
+                        ours_kind,
+                        &mut ours_line,
+                        height,
+                    );
+                    push_side(
+                        &mut rows.theirs,
+                        &conflict.theirs,
+                        theirs_kind,
+                        &mut theirs_line,
+                        height,
+                    );
+
+                    // Keep empty conflicts selectable
+                    if height == 0 {
+                        rows.base.push(DiffLine::new("", DiffKind::Blank, None));
+                        rows.ours.push(DiffLine::new("", DiffKind::Blank, None));
+                        rows.theirs.push(DiffLine::new("", DiffKind::Blank, None));
+                    }
+
+                    rows.regions.push(start..rows.ours.len());
+                }
+            }
+        }
+
+        self.rows = rows;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ConflictSide {
+    Ours,
+    Base,
+    Theirs,
+}
+
+/// Pushes one side of a conflict, padding with blank rows up to `height`
+fn push_side(
+    rows: &mut Vec<DiffLine>,
+    lines: &[String],
+    kind: DiffKind,
+    line_number: &mut usize,
+    height: usize,
+) {
+    for line in lines {
+        rows.push(DiffLine::new(line, kind, Some(*line_number)));
+        *line_number += 1;
+    }
+    for _ in lines.len()..height {
+        rows.push(DiffLine::new("", DiffKind::Blank, None))
+    }
+}
+
+/// Returns the label following a conflict marker, e.g. `HEAD` for `<<<<<<< HEAD`
+fn marker_label<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(marker)?;
+    if rest.is_empty() {
+        return Some(rest);
+    }
+    rest.strip_prefix(' ')
+}
+
+fn format_marker(marker: &str, label: &str) -> String {
+    if label.is_empty() {
+        marker.to_string()
+    } else {
+        format!("{} {}", marker, label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFLICTED: &str = "\
+one
+<<<<<<< HEAD
+ours
+||||||| base
+original
+=======
+theirs
+more theirs
+>>>>>>> feature
+two
+";
+
+    #[test]
+    fn test_parse_aligns_sides() {
+        let file = ConflictFile::parse(Path::new("file.txt"), CONFLICTED).unwrap();
+
+        assert_eq!(file.conflict_count(), 1);
+        assert_eq!(file.rows().len(), 4);
+        assert_eq!(file.rows().regions().to_vec(), vec![1..3]);
+        assert_eq!(file.rows().ours()[2].content(), "");
+        assert_eq!(file.rows().theirs()[2].content(), "more theirs");
+        assert_eq!(file.rows().base()[1].content(), "original");
+        assert_eq!(*file.rows().theirs()[3].line_number(), Some(4));
+    }
+
+    #[test]
+    fn test_conflict_near_row() {
+        let content = format!(
+            "{}<<<<<<< main\nmine\n=======\nyours\n>>>>>>> topic\nthree\n",
+            CONFLICTED
+        );
+        let file = ConflictFile::parse(Path::new("file.txt"), &content).unwrap();
+        assert_eq!(file.rows().regions().to_vec(), vec![1..3, 4..5]);
+
+        let labels = |row| {
+            let conflict = file.conflict_near_row(row).unwrap();
+            (conflict.ours_label(), conflict.theirs_label())
+        };
+        assert_eq!(labels(0), ("HEAD", "feature"));
+        assert_eq!(labels(3), ("HEAD", "feature"));
+        assert_eq!(labels(4), ("main", "topic"));
+        assert_eq!(labels(5), ("main", "topic"));
+    }
+
+    #[test]
+    fn test_parse_stray_markers() {
+        let parse_error = |content| {
+            ConflictFile::parse(Path::new("file.txt"), content)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            parse_error("one\n=======\ntwo\n"),
+            "file.txt:2: ======= without an opening <<<<<<< marker"
+        );
+        assert_eq!(
+            parse_error("one\n>>>>>>> feature\n"),
+            "file.txt:2: >>>>>>> feature without an opening <<<<<<< marker"
+        );
+        assert_eq!(
+            parse_error("one\n<<<<<<< HEAD\nours\n=======\n"),
+            "file.txt:2: conflict is never closed with a >>>>>>> marker"
+        );
+    }
+
+    #[test]
+    fn test_resolved_content() {
+        let mut file = ConflictFile::parse(Path::new("file.txt"), CONFLICTED).unwrap();
+        assert_eq!(file.resolved_content(), CONFLICTED);
+
+        file.resolve(0, Resolution::Both);
+        assert_eq!(
+            file.resolved_content(),
+            "one\nours\ntheirs\nmore theirs\ntwo\n"
+        );
+        assert_eq!(file.unresolved_count(), 0);
+
+        file.resolve(0, Resolution::Theirs);
+        assert_eq!(file.resolved_content(), "one\ntheirs\nmore theirs\ntwo\n");
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// How a file differs between two directory trees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    /// Only in the new tree
+    Added,
+    /// Only in the old tree
+    Removed,
+    Changed,
+    Identical,
+}
+
+/// A file found in either tree, by its path relative to the tree roots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePair {
+    /// Relative path with `/` separators, as in git diffs
+    path: String,
+    change: FileChange,
+}
+
+impl FilePair {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn change(&self) -> FileChange {
+        self.change
+    }
+}
+
+/// Walks both trees and pairs their files by relative path, sorted by path. Files and
+/// directories matching any of the `ignore` globs, by relative path or by name, are left out.
+pub fn pair_files(old: &Path, new: &Path, ignore: &[String]) -> Result<Vec<FilePair>> {
+    let ignore = build_globs(ignore)?;
+    let old_files = walk(old, &ignore)?;
+    let new_files = walk(new, &ignore)?;
+
+    let mut paths: Vec<&String> = old_files.keys().chain(new_files.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let change = match (old_files.get(path), new_files.get(path)) {
+                (Some(old), Some(new)) if same_content(old, new)? => FileChange::Identical,
+                (Some(_), Some(_)) => FileChange::Changed,
+                (Some(_), None) => FileChange::Removed,
+                (None, _) => FileChange::Added,
+            };
+            Ok(FilePair {
+                path: path.clone(),
+                change,
+            })
+        })
+        .collect()
+}
+
+fn build_globs(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder
+            .add(Glob::new(pattern).with_context(|| format!("Invalid ignore glob '{}'", pattern))?);
+    }
+    Ok(builder.build()?)
+}
+
+/// Files under `root` by relative path, skipping ignored entries and not following symlinks
+fn walk(root: &Path, ignore: &GlobSet) -> Result<BTreeMap<String, PathBuf>> {
+    let relative = |path: &Path| {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+
+    let mut files = BTreeMap::new();
+    let entries = WalkDir::new(root).into_iter().filter_entry(|entry| {
+        entry.depth() == 0
+            || !(ignore.is_match(relative(entry.path())) || ignore.is_match(entry.file_name()))
+    });
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to read {}", root.display()))?;
+        // Symlinks, even to directories, are compared by their target rather than followed
+        if entry.path_is_symlink() || !entry.file_type().is_dir() {
+            files.insert(relative(entry.path()), entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+/// A file's content, or a symlink's target path as git shows it, without following the link
+pub fn read_entry(path: &Path) -> Result<Vec<u8>> {
+    if path.is_symlink() {
+        let target = std::fs::read_link(path)
+            .with_context(|| format!("Failed to read link {}", path.display()))?;
+        return Ok(target.to_string_lossy().into_owned().into_bytes());
+    }
+    std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+fn same_content(old: &Path, new: &Path) -> Result<bool> {
+    if !old.is_symlink()
+        && !new.is_symlink()
+        && std::fs::metadata(old)?.len() != std::fs::metadata(new)?.len()
+    {
+        return Ok(false);
+    }
+    Ok(read_entry(old)? == read_entry(new)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_tree(root: &Path, files: &[(&str, &str)]) {
+        let _ = std::fs::remove_dir_all(root);
+        for (path, content) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_pair_files() {
+        let root = std::env::temp_dir().join("diff-tool-test-pair-files");
+        let (old, new) = (root.join("old"), root.join("new"));
+        write_tree(
+            &old,
+            &[
+                ("a.rs", "a\n"),
+                ("src/b.rs", "b\n"),
+                ("src/gone.rs", "c\n"),
+                ("target/out", "x\n"),
+            ],
+        );
+        write_tree(
+            &new,
+            &[
+                ("a.rs", "a\n"),
+                ("src/b.rs", "b2\n"),
+                ("src/new.rs", "d\n"),
+                ("target/out", "y\n"),
+                ("Cargo.lock", "z\n"),
+            ],
+        );
+
+        let pairs = pair_files(&old, &new, &["target".to_string(), "*.lock".to_string()]);
+        let _ = std::fs::remove_dir_all(&root);
+
+        let pairs: Vec<(&str, FileChange)> = pairs
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|pair| (pair.path(), pair.change()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("a.rs", FileChange::Identical),
+                ("src/b.rs", FileChange::Changed),
+                ("src/gone.rs", FileChange::Removed),
+                ("src/new.rs", FileChange::Added),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_pair_files_symlinked_directories() {
+        use std::os::unix::fs::symlink;
+
+        let root = std::env::temp_dir().join("diff-tool-test-pair-symlinks");
+        let (old, new) = (root.join("old"), root.join("new"));
+        write_tree(&old, &[("sub/a.rs", "a\n"), ("other/b.rs", "b\n")]);
+        write_tree(&new, &[("sub/a.rs", "a\n"), ("other/b.rs", "b\n")]);
+        symlink("sub", old.join("same")).unwrap();
+        symlink("sub", new.join("same")).unwrap();
+        symlink("sub", old.join("moved")).unwrap();
+        symlink("other", new.join("moved")).unwrap();
+
+        let pairs = pair_files(&old, &new, &[]);
+        let target = read_entry(&new.join("moved"));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let pairs: Vec<(&str, FileChange)> = pairs
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|pair| (pair.path(), pair.change()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("moved", FileChange::Changed),
+                ("other/b.rs", FileChange::Identical),
+                ("same", FileChange::Identical),
+                ("sub/a.rs", FileChange::Identical),
+            ]
+        );
+        assert_eq!(target.unwrap(), b"other");
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{anyhow, Result};
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Text decoded from a file's raw bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    text: String,
+    encoding: &'static Encoding,
+    /// Whether any byte sequences were invalid in `encoding`, they show as `�`
+    malformed: bool,
+}
+
+impl Decoded {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn into_text(self) -> String {
+        self.text
+    }
+
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    pub fn malformed(&self) -> bool {
+        self.malformed
+    }
+}
+
+/// Decodes bytes with the `--encoding` override when given, otherwise with the encoding from a
+/// byte order mark, then UTF-8 if the bytes are valid, then a statistical guess. A BOM is
+/// dropped, and invalid sequences become U+FFFD replacement characters.
+pub fn decode(bytes: &[u8], encoding: Option<&'static Encoding>) -> Decoded {
+    let encoding = encoding.unwrap_or_else(|| detect(bytes));
+    let (text, malformed) = encoding.decode_with_bom_removal(bytes);
+
+    if malformed {
+        tracing::warn!("Invalid {} byte sequences shown as �", encoding.name());
+    }
+
+    Decoded {
+        text: text.into_owned(),
+        encoding,
+        malformed,
+    }
+}
+
+/// Guesses the encoding of some text
+pub fn detect(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+/// Whether bytes that look binary are UTF-16 text, going by a BOM or the `--encoding` override
+pub fn is_utf16(bytes: &[u8], encoding: Option<&'static Encoding>) -> bool {
+    let encoding = encoding.or_else(|| Encoding::for_bom(bytes).map(|(encoding, _)| encoding));
+    encoding.is_some_and(|encoding| encoding == UTF_16LE || encoding == UTF_16BE)
+}
+
+/// Looks up an encoding by a WHATWG label, e.g. `latin1`, `shift_jis` or `utf-16le`
+pub fn parse_label(label: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| anyhow!("Unknown encoding '{}'", label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_detects_encoding() {
+        let latin1 = b"caf\xe9 cr\xe8me br\xfbl\xe9e, \xe0 la fran\xe7aise\n";
+        let decoded = decode(latin1, None);
+        assert_eq!(decoded.text(), "café crème brûlée, à la française\n");
+        assert_eq!(decoded.encoding().name(), "windows-1252");
+
+        let utf16 = b"\xff\xfeh\x00i\x00";
+        assert!(is_utf16(utf16, None));
+        assert_eq!(decode(utf16, None).text(), "hi");
+    }
+
+    #[test]
+    fn test_decode_marks_invalid_bytes() {
+        let decoded = decode(b"ok \xff\n", Some(UTF_8));
+        assert_eq!(decoded.text(), "ok \u{fffd}\n");
+        assert!(decoded.malformed());
+    }
+}
@@ -1,224 +1,158 @@
 use std::{
-    path::Path,
+    io,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 
-#[derive(Default, Debug)]
-pub struct Diff {
-    old_diff: Vec<DiffLine>,
-    current_diff: Vec<DiffLine>,
+use thiserror::Error;
+
+/// Ways running `git diff` can fail
+#[derive(Debug, Error)]
+pub enum GitError {
+    #[error("git was not found, is it installed and on your PATH?")]
+    GitNotFound,
+    #[error("failed to run git: {0}")]
+    Spawn(#[source] io::Error),
+    #[error("{} is not inside a git repository", .0.display())]
+    NotARepository(PathBuf),
+    #[error("{} does not exist", .0.display())]
+    PathNotFound(PathBuf),
+    #[error("{} is not valid UTF-8 and can't be passed to git", .0.display())]
+    NonUtf8Path(PathBuf),
+    #[error("git diff failed with {status}: {stderr}")]
+    Failed { status: String, stderr: String },
 }
 
-impl Diff {
-    pub fn longest_diff_len(&self) -> usize {
-        let old_diff = self.old_diff.len();
-        let current_diff = self.current_diff.len();
-
-        std::cmp::max(old_diff, current_diff) - 1
-    }
-
-    pub fn old_diff(&self) -> &[DiffLine] {
-        &self.old_diff
-    }
-
-    pub fn current_diff(&self) -> &[DiffLine] {
-        &self.current_diff
-    }
-
-    pub fn largest_line_number_char_len(&self) -> u16 {
-        let (old_diff, current_diff) = self.largest_line_number();
-
-        let largest_line_number = std::cmp::max(old_diff, current_diff);
-
-        let length = std::cmp::min(largest_line_number.to_string().len(), u16::MAX.into());
-        length.try_into().unwrap_or(4)
-    }
-
-    /// Gets the largest line number from each diff
-    fn largest_line_number(&self) -> (usize, usize) {
-        let old_diff = self
-            .old_diff
-            .iter()
-            .map(|x| x.line_number().unwrap_or(0))
-            .max()
-            .unwrap_or(0);
-
-        let current_diff = self
-            .old_diff
-            .iter()
-            .map(|x| x.line_number().unwrap_or(0))
-            .max()
-            .unwrap_or(0);
-
-        (old_diff, current_diff)
-    }
-
-    pub fn parse_diff(diff_string: &str) -> Self {
-        let lines = diff_string.split("\n");
-
-        let mut diff = Self::default();
-
-        let mut start = false;
-        let mut additions = 0;
-        let mut removals = 0;
-        let mut diff_one_line = 1;
-        let mut diff_two_line = 1;
-
-        for line in lines {
-            if line.starts_with("@@") && line.ends_with("@@") {
-                start = true;
-                continue;
-            }
-
-            if !start {
-                continue;
-            }
-
-            let (prefix, content) = remove_first_char(line);
-
-            match prefix {
-                '+' => {
-                    diff.current_diff.push(DiffLine::new(
-                        content,
-                        DiffKind::Addition,
-                        Some(diff_two_line),
-                    ));
-                    diff_two_line += 1;
-                    if removals > 0 {
-                        removals -= 1
-                    } else {
-                        additions += 1
-                    }
-                }
-
-                '-' => {
-                    diff.old_diff.push(DiffLine::new(
-                        content,
-                        DiffKind::Removal,
-                        Some(diff_one_line),
-                    ));
-                    diff_one_line += 1;
-                    removals += 1
-                }
-                _ => {
-                    for _ in 0..removals {
-                        diff.current_diff
-                            .push(DiffLine::new("", DiffKind::Blank, None))
-                    }
-
-                    removals = 0;
-
-                    for _ in 0..additions {
-                        diff.old_diff.push(DiffLine::new("", DiffKind::Blank, None))
-                    }
-
-                    additions = 0;
-
-                    diff.old_diff.push(DiffLine::new(
-                        content,
-                        DiffKind::Neutral,
-                        Some(diff_one_line),
-                    ));
-                    diff_one_line += 1;
-                    diff.current_diff.push(DiffLine::new(
-                        content,
-                        DiffKind::Neutral,
-                        Some(diff_two_line),
-                    ));
-                    diff_two_line += 1
-                }
-            }
-        }
-
-        for _ in 0..removals {
-            diff.current_diff
-                .push(DiffLine::new("", DiffKind::Blank, None))
-        }
+/// Performs 'git diff -M -C -U1000 <filename>' or 'git -C [path] diff -M -C -U1000 <filename>' and returns the raw output,
+/// which is in the file's own encoding. `extra_args` are further diff options, e.g. `--ignore-all-space`.
+pub fn get_raw_diff(path: &Path, dir_flag: bool, extra_args: &[&str]) -> Result<Vec<u8>, GitError> {
+    let mut args = if !dir_flag {
+        vec!["diff", "-M", "-C", "-U1000", "--", to_str(path)?]
+    } else {
+        // A directory is diffed from inside, so it can be the top of the repository
+        let (parent, file_name) = if path.is_dir() {
+            (path, Path::new("."))
+        } else {
+            let parent = path.parent().unwrap_or_else(|| Path::new(""));
+            let file_name = path
+                .file_name()
+                .map(Path::new)
+                .ok_or_else(|| GitError::PathNotFound(path.to_path_buf()))?;
+            (parent, file_name)
+        };
+        vec![
+            "-C",
+            to_str(parent)?,
+            "diff",
+            "-M",
+            "-C",
+            "-U1000",
+            "--",
+            to_str(file_name)?,
+        ]
+    };
+    let separator = args.len() - 2;
+    args.splice(separator..separator, extra_args.iter().copied());
 
-        for _ in 0..additions {
-            diff.old_diff.push(DiffLine::new("", DiffKind::Blank, None))
+    // Process git diff <filename> command and save the stdout response
+    let output = Command::new("git")
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => GitError::GitNotFound,
+            _ => GitError::Spawn(e),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        tracing::error!("git diff failed: {}", stderr);
+        let lowercase = stderr.to_lowercase();
+        if lowercase.contains("not a git repository") || lowercase.contains("outside repository") {
+            return Err(GitError::NotARepository(path.to_path_buf()));
         }
-
-        diff
-    }
-}
-
-#[derive(Debug, Clone, Default)]
-pub struct DiffLine {
-    content: String,
-    kind: DiffKind,
-    line_number: Option<usize>,
-}
-
-impl DiffLine {
-    fn new(content: &str, kind: DiffKind, line_number: Option<usize>) -> Self {
-        let content = content.to_string();
-        Self {
-            content,
-            kind,
-            line_number,
+        if stderr.contains("cannot change to") {
+            return Err(GitError::PathNotFound(path.to_path_buf()));
         }
+        return Err(GitError::Failed {
+            status: output.status.to_string(),
+            stderr,
+        });
     }
 
-    pub fn content(&self) -> &str {
-        &self.content
+    // Git reports nothing for a pathspec that matches no file
+    if output.stdout.is_empty() && !path.exists() {
+        return Err(GitError::PathNotFound(path.to_path_buf()));
     }
 
-    pub fn kind(&self) -> &DiffKind {
-        &self.kind
-    }
-
-    pub fn line_number(&self) -> &Option<usize> {
-        &self.line_number
-    }
+    Ok(output.stdout)
 }
 
-#[derive(Debug, Clone, Copy, Default)]
-pub enum DiffKind {
-    Addition,
-    Removal,
-    Neutral,
-    #[default]
-    Blank,
-}
+/// Reads a file's staged content with 'git -C [path] show :./<filename>', the old side of
+/// `get_raw_diff`. `None` when the file isn't in the index.
+pub fn get_index_content(path: &Path) -> Result<Option<Vec<u8>>, GitError> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .map(Path::new)
+        .ok_or_else(|| GitError::PathNotFound(path.to_path_buf()))?;
+    let object = format!(":./{}", to_str(file_name)?);
 
-impl DiffKind {
-    pub fn value(&self) -> &str {
-        match self {
-            DiffKind::Addition => "+",
-            DiffKind::Removal => "-",
-            DiffKind::Neutral => " ",
-            DiffKind::Blank => " ",
-        }
-    }
-}
+    let output = Command::new("git")
+        .args(["-C", to_str(parent)?, "show", &object])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => GitError::GitNotFound,
+            _ => GitError::Spawn(e),
+        })?;
 
-/// Performs 'git diff -U1000 <filename>' or 'git -C [path] diff -U1000 <filename>' and returns the result as a string
-pub fn get_raw_diff(path: &Path, dir_flag: bool) -> String {
-    let args = if !dir_flag {
-        vec!["diff", "-U1000", path.to_str().unwrap()]
-    } else {
-        vec![
-            "-C",
-            path.parent().unwrap().to_str().unwrap(),
-            "diff",
-            "-U1000",
-            path.file_name().unwrap().to_str().unwrap(),
-        ]
-    };
+    Ok(output.status.success().then_some(output.stdout))
+}
 
-    // Process git diff <filename> command and save the stdout response
+/// Top level directory of the repository containing the directory `dir`, which the paths in
+/// `git diff` output are relative to
+pub fn get_repo_root(dir: &Path) -> Result<PathBuf, GitError> {
     let output = Command::new("git")
-        .args(args)
+        .args(["-C", to_str(dir)?, "rev-parse", "--show-toplevel"])
         .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .output()
-        .expect("Failed to execute git diff");
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => GitError::GitNotFound,
+            _ => GitError::Spawn(e),
+        })?;
 
-    // Convert stdout response to a string and return
-    String::from_utf8(output.stdout).expect("UTF8 data to convert to string")
+    if !output.status.success() {
+        return Err(GitError::NotARepository(dir.to_path_buf()));
+    }
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim_end(),
+    ))
+}
+
+/// Paths are passed to git as arguments, which must be UTF-8 here
+fn to_str(path: &Path) -> Result<&str, GitError> {
+    path.to_str()
+        .ok_or_else(|| GitError::NonUtf8Path(path.to_path_buf()))
 }
 
-fn remove_first_char(string: &str) -> (char, &str) {
-    let mut chars = string.chars();
-    (chars.next().unwrap_or(' '), chars.as_str())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_path() {
+        let path = std::env::temp_dir().join("diff-tool-missing-file.rs");
+        let error = get_raw_diff(&path, true, &[]).unwrap_err();
+        assert!(matches!(
+            error,
+            GitError::PathNotFound(_) | GitError::NotARepository(_)
+        ));
+    }
 }
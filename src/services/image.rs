@@ -0,0 +1,183 @@
+use anyhow::{Context, Result};
+use image::RgbaImage;
+use resvg::{
+    tiny_skia,
+    usvg::{self, TreeParsing, TreePostProc},
+};
+use std::fmt;
+
+use crate::diff::{format_size, format_size_change};
+
+/// Extensions of the files shown as an image preview
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "webp", "ico", "svg"];
+
+/// Longest side SVGs are rasterised to
+const SVG_MAX_SIZE: f32 = 1024.0;
+
+/// One decoded version of an image file
+#[derive(Clone)]
+pub struct ImageSide {
+    image: RgbaImage,
+    /// Size of the encoded file in bytes
+    size: u64,
+}
+
+/// The old and new versions of an image file, `None` for an added or deleted side
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    old: Option<ImageSide>,
+    new: Option<ImageSide>,
+}
+
+impl ImageSide {
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.image.dimensions()
+    }
+}
+
+impl fmt::Debug for ImageSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImageSide")
+            .field("dimensions", &self.dimensions())
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl ImageDiff {
+    /// Decodes both versions of the image at `path`, whose extension picks the decoder
+    pub fn decode(path: &str, old: Option<&[u8]>, new: Option<&[u8]>) -> Result<Self> {
+        let decode_side = |bytes: Option<&[u8]>| -> Result<Option<ImageSide>> {
+            let Some(bytes) = bytes else {
+                return Ok(None);
+            };
+            Ok(Some(ImageSide {
+                image: decode(path, bytes)?,
+                size: bytes.len() as u64,
+            }))
+        };
+
+        Ok(Self {
+            old: decode_side(old).with_context(|| format!("Failed to decode old {}", path))?,
+            new: decode_side(new).with_context(|| format!("Failed to decode new {}", path))?,
+        })
+    }
+
+    pub fn old_side(&self) -> Option<&ImageSide> {
+        self.old.as_ref()
+    }
+
+    pub fn new_side(&self) -> Option<&ImageSide> {
+        self.new.as_ref()
+    }
+
+    /// Dimension and size changes, e.g. `64×64 → 128×64 (+64×+0), 1.0 KiB → 2.0 KiB (+1.0 KiB)`
+    pub fn summary(&self) -> String {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => {
+                let (old_width, old_height) = old.dimensions();
+                let (new_width, new_height) = new.dimensions();
+                format!(
+                    "{}×{} → {}×{} ({:+}×{:+}), {}",
+                    old_width,
+                    old_height,
+                    new_width,
+                    new_height,
+                    i64::from(new_width) - i64::from(old_width),
+                    i64::from(new_height) - i64::from(old_height),
+                    format_size_change(old.size, new.size)
+                )
+            }
+            (None, Some(side)) => format!("Added {}", describe(side)),
+            (Some(side), None) => format!("Deleted {}", describe(side)),
+            (None, None) => "No image".to_string(),
+        }
+    }
+}
+
+/// Whether a file is previewed as an image, going by its extension
+pub fn is_image_path(path: &str) -> bool {
+    std::path::Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            IMAGE_EXTENSIONS
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        })
+}
+
+fn describe(side: &ImageSide) -> String {
+    let (width, height) = side.dimensions();
+    format!("{}×{}, {}", width, height, format_size(side.size))
+}
+
+fn decode(path: &str, bytes: &[u8]) -> Result<RgbaImage> {
+    if path.to_lowercase().ends_with(".svg") {
+        return rasterise_svg(bytes);
+    }
+    Ok(image::load_from_memory(bytes)?.to_rgba8())
+}
+
+/// Renders an SVG to pixels, scaled so its longest side is at most `SVG_MAX_SIZE`
+fn rasterise_svg(bytes: &[u8]) -> Result<RgbaImage> {
+    let mut tree = usvg::Tree::from_data(bytes, &usvg::Options::default())?;
+    tree.postprocess(Default::default());
+
+    let size = tree.size;
+    let scale = (SVG_MAX_SIZE / size.width().max(size.height())).min(1.0);
+    let width = (size.width() * scale).ceil() as u32;
+    let height = (size.height() * scale).ceil() as u32;
+
+    let mut pixmap =
+        tiny_skia::Pixmap::new(width.max(1), height.max(1)).context("SVG has no area to render")?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    // tiny-skia works in premultiplied alpha
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    RgbaImage::from_raw(pixmap.width(), pixmap.height(), pixels)
+        .context("SVG rendered to an unexpected size")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+<rect width="20" height="10" fill="red"/></svg>"#;
+
+    #[test]
+    fn test_decode_svg() {
+        let images = ImageDiff::decode("logo.svg", None, Some(SVG.as_bytes())).unwrap();
+        let new = images.new_side().unwrap();
+
+        assert_eq!(new.dimensions(), (20, 10));
+        assert_eq!(new.image().get_pixel(5, 5).0, [255, 0, 0, 255]);
+        assert_eq!(images.summary(), format!("Added 20×10, {} B", SVG.len()));
+    }
+
+    #[test]
+    fn test_is_image_path() {
+        assert!(is_image_path("assets/Logo.PNG"));
+        assert!(!is_image_path("src/main.rs"));
+    }
+}
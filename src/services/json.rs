@@ -0,0 +1,94 @@
+//! JSON output of the parsed diff model, for tooling that wants the aligned side by side rows.
+//!
+//! The document is versioned by `schema_version`, which is bumped whenever a field is removed or
+//! changes meaning. New fields may be added without a bump.
+//!
+//! ```json
+//! {
+//!   "schema_version": 1,
+//!   "files": [
+//!     {
+//!       "old_path": "src/main.rs",        // null for an added file
+//!       "new_path": "src/main.rs",        // null for a deleted file
+//!       "status": "modified",             // modified | added | deleted | renamed | copied
+//!       "old_mode": null,                 // file modes from git's extended headers, e.g. "100644"
+//!       "new_mode": null,
+//!       "similarity": null,               // percentage unchanged by a rename or copy
+//!       "old_revision": "1111111",        // abbreviated blob ids from git's `index` line
+//!       "new_revision": "2222222",
+//!       "encoding": null,                 // text encoding when not UTF-8, e.g. "windows-1252"
+//!       "additions": 1,
+//!       "removals": 1,
+//!       "binary": null,                   // { "old_size", "new_size", "mime" } for binary files,
+//!                                         // whose rows are a hex dump
+//!       "hunks": [
+//!         {
+//!           "old_start": 1, "old_len": 3, "new_start": 1, "new_len": 3,
+//!           "context": "fn main() {",     // text after the `@@` header
+//!           "rows": { "start": 0, "end": 3 } // aligned rows covered, end exclusive
+//!         }
+//!       ],
+//!       "moved": [                        // blocks of lines moved within the file
+//!         { "old_rows": { "start": 4, "end": 7 }, "new_rows": { "start": 0, "end": 3 } }
+//!       ],
+//!       "rows": [
+//!         {
+//!           "old": {
+//!             "content": "let a = 1;",
+//!             "kind": "removal",          // addition | removal | neutral | conflict | moved | blank
+//!             "line_number": 2,           // null for blank padding
+//!             "highlights": [ { "start": 8, "end": 9 } ] // changed byte ranges
+//!           },
+//!           "new": { "content": "let a = 2;", "kind": "addition", "line_number": 2, "highlights": [] }
+//!         }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::diff::Diff;
+
+/// Version of the JSON document layout
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Document<'a> {
+    schema_version: u32,
+    files: &'a [Diff],
+}
+
+/// Serialises the diffs as a pretty printed JSON document
+pub fn to_json(diffs: &[Diff]) -> Result<String> {
+    let document = Document {
+        schema_version: SCHEMA_VERSION,
+        files: diffs,
+    };
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() -> Result<()> {
+        let diffs = Diff::parse_patch(
+            "--- a/file.txt\n+++ b/file.txt\n@@ -1,2 +1,2 @@\n same\n-let a = 1;\n+let a = 2;\n",
+        );
+        let json: serde_json::Value = serde_json::from_str(&to_json(&diffs)?)?;
+
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        let file = &json["files"][0];
+        assert_eq!(file["new_path"], "file.txt");
+        assert_eq!(file["rows"][1]["old"]["kind"], "removal");
+        assert_eq!(file["rows"][1]["new"]["line_number"], 2);
+        assert_eq!(file["rows"][1]["new"]["highlights"][0]["start"], 8);
+        assert_eq!(file["hunks"][0]["rows"]["end"], 2);
+
+        Ok(())
+    }
+}
@@ -1,13 +1,22 @@
-use super::config::get_data_dir;
-use anyhow::Result;
+use super::config::Options;
+use anyhow::{Context as _, Result};
 use std::{
-    io::Write,
-    sync::{Arc, Mutex},
+    collections::VecDeque,
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
 };
-use tracing::{Event, Subscriber};
 use tracing_error::ErrorLayer;
 use tracing_subscriber::{
     self,
+    filter::{EnvFilter, LevelFilter},
     fmt::MakeWriter,
     layer::{Context, SubscriberExt},
     util::SubscriberInitExt,
@@ -17,95 +26,283 @@ use tracing_subscriber::{
 lazy_static::lazy_static! {
   pub static ref PROJECT_NAME: String = env!("CARGO_CRATE_NAME").to_uppercase().to_string();
   pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
-  pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
 
-pub type Logs = Arc<Mutex<Vec<String>>>;
+/// Most records kept for the console, the oldest being dropped for new ones
+pub const CONSOLE_CAPACITY: usize = 1000;
 
-#[derive(Clone)]
-pub struct VecWriter {
-    logs: Logs,
+pub type Logs = Arc<Mutex<VecDeque<LogRecord>>>;
+
+/// A log event kept for the console
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    level: Level,
+    target: String,
+    timestamp: SystemTime,
+    /// The message followed by any other fields, e.g. `Showing a.rs path="a.rs"`
+    message: String,
 }
 
-impl Write for VecWriter {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let msg = String::from_utf8_lossy(buf);
-        let mut logs = self.logs.lock().unwrap();
+impl LogRecord {
+    pub fn new(level: Level, target: &str, message: &str) -> Self {
+        Self {
+            level,
+            target: target.to_string(),
+            timestamp: SystemTime::now(),
+            message: message.to_string(),
+        }
+    }
 
-        logs.push(msg.into_owned());
+    pub fn level(&self) -> Level {
+        self.level
+    }
 
-        Ok(buf.len())
+    pub fn target(&self) -> &str {
+        &self.target
     }
 
-    fn flush(&mut self) -> std::io::Result<()> {
-        Ok(())
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
     }
-}
 
-impl<'writer> MakeWriter<'writer> for VecWriter {
-    type Writer = VecWriter;
+    /// Time of day the record was logged, `HH:MM:SS` in UTC
+    pub fn time_of_day(&self) -> String {
+        let seconds = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            % (24 * 60 * 60);
+        format!(
+            "{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    }
 
-    fn make_writer(&'writer self) -> Self::Writer {
-        self.clone()
+    pub fn message(&self) -> &str {
+        &self.message
     }
 }
 
-impl<S> Layer<S> for VecWriter
+/// Collects log events into [`Logs`] for the console
+#[derive(Clone)]
+pub struct ConsoleLayer {
+    logs: Logs,
+}
+
+impl<S> Layer<S> for ConsoleLayer
 where
     S: Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
 {
     fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
-        // Log to custom destination (e.g., Vec)
-        self.logs
-            .lock()
-            .unwrap()
-            .push(format!("{}", event.metadata().name()));
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        let record = LogRecord::new(*metadata.level(), metadata.target(), &visitor.message);
+        let mut logs = self.logs.lock().unwrap();
+        if logs.len() >= CONSOLE_CAPACITY {
+            logs.pop_front();
+        }
+        logs.push_back(record);
     }
 }
 
-impl VecWriter {
+impl ConsoleLayer {
     pub fn new(logs: Logs) -> Self {
         Self { logs }
     }
 }
 
-pub fn initialize_logging(vec_writer: VecWriter) -> Result<()> {
-    let directory = get_data_dir()?;
-    std::fs::create_dir_all(directory.clone())?;
-    let log_path = directory.join(LOG_FILE.clone());
-    let file_writer = std::fs::File::create(log_path)?;
-
-    std::env::set_var(
-        "RUST_LOG",
-        std::env::var("RUST_LOG")
-            .or_else(|_| std::env::var(LOG_ENV.clone()))
-            .unwrap_or_else(|_| format!("{}=info", env!("CARGO_CRATE_NAME"))),
-    );
-
-    let file_subscriber = tracing_subscriber::fmt::layer()
-        .with_file(true)
-        .with_line_number(true)
-        .with_writer(file_writer)
-        .with_target(false)
-        .with_ansi(false)
-        .with_filter(tracing_subscriber::filter::EnvFilter::from_default_env());
-
-    let console_suscriber = tracing_subscriber::fmt::layer()
-        .with_file(false)
-        .with_line_number(false)
-        .with_writer(vec_writer)
-        .with_target(false)
-        .with_ansi(false)
-        .with_filter(tracing_subscriber::filter::EnvFilter::from_default_env());
+/// The records shown in the console, those at or above `level`, read while the logs are locked.
+/// Nothing may be logged while it's held.
+pub struct ConsoleRecords<'a> {
+    logs: MutexGuard<'a, VecDeque<LogRecord>>,
+    level: Option<Level>,
+}
+
+impl<'a> ConsoleRecords<'a> {
+    pub fn new(logs: &'a Logs, level: Option<Level>) -> Self {
+        Self {
+            logs: logs.lock().unwrap(),
+            level,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LogRecord> {
+        self.logs
+            .iter()
+            .filter(|record| self.level.is_none_or(|level| record.level() <= level))
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+/// Formats an event's `message` field, then its other fields as `name=value`
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message.insert_str(0, &format!("{:?}", value));
+        } else {
+            self.message
+                .push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.insert_str(0, value);
+        } else {
+            self.message
+                .push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+}
+
+/// Sends logs to the console and, unless turned off, to the log file, filtered by the
+/// `log_level` option, then `RUST_LOG`, then `DIFF_TOOL_LOGLEVEL`, at `info` by default
+pub fn initialize_logging(console: ConsoleLayer, options: &Options) -> Result<()> {
+    let directive = filter_directive(options.log_level());
+    let filter = || {
+        EnvFilter::try_new(&directive).with_context(|| format!("Invalid log level '{}'", directive))
+    };
+
+    let file_subscriber = match options.log_file()? {
+        Some(path) => {
+            let writer = RotatingFile::open(path, options.log_max_size(), options.log_keep())?;
+            let layer = tracing_subscriber::fmt::layer()
+                .with_file(true)
+                .with_line_number(true)
+                .with_writer(writer)
+                .with_target(false)
+                .with_ansi(false)
+                .with_filter(filter()?);
+            Some(layer)
+        }
+        None => None,
+    };
 
     tracing_subscriber::registry()
         .with(file_subscriber)
         .with(ErrorLayer::default())
-        .with(console_suscriber)
+        .with(console.with_filter(filter()?))
         .init();
     Ok(())
 }
 
+/// The filter for the given level, applied to diff-tool's own logs when it's just a level name
+fn filter_directive(level: Option<&str>) -> String {
+    let level = level
+        .map(ToString::to_string)
+        .or_else(|| std::env::var("RUST_LOG").ok())
+        .or_else(|| std::env::var(LOG_ENV.clone()).ok())
+        .unwrap_or_else(|| "info".to_string());
+    if level.parse::<LevelFilter>().is_ok() {
+        format!("{}={}", env!("CARGO_CRATE_NAME"), level)
+    } else {
+        level
+    }
+}
+
+/// The log file, opened for appending. Once a write would take it past `max_size` bytes it's
+/// moved to `<name>.1`, the older files shifting up to `<name>.<keep>` and the oldest deleted.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    keep: usize,
+    state: Mutex<OpenFile>,
+}
+
+struct OpenFile {
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, max_size: u64, keep: usize) -> Result<Self> {
+        if let Some(directory) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(directory)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open the log file {}", path.display()))?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_size,
+            keep,
+            state: Mutex::new(OpenFile { file, size }),
+        })
+    }
+
+    /// Path of the `n`th most recent rotated file
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&self, state: &mut OpenFile) -> io::Result<()> {
+        for n in (1..self.keep).rev() {
+            let from = self.rotated(n);
+            if from.exists() {
+                std::fs::rename(from, self.rotated(n + 1))?;
+            }
+        }
+        if self.keep > 0 {
+            std::fs::rename(&self.path, self.rotated(1))?;
+        }
+        state.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        state.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for &RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let size = state.size + buf.len() as u64;
+        if self.max_size > 0 && state.size > 0 && size > self.max_size {
+            self.rotate(&mut state)?;
+        }
+        let written = state.file.write(buf)?;
+        state.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.file.flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for RotatingFile {
+    type Writer = &'a RotatingFile;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
+
 /// Similar to the `std::dbg!` macro, but generates `tracing` events rather
 /// than printing to stdout.
 ///
@@ -132,16 +329,82 @@ macro_rules! trace_dbg {
     };
 }
 
-//
-// pub fn init_logging(writer: VecWriter, level: LevelFilter) {
-//     // let data_dir = get_data_dir().unwrap();
-//     // std::fs::create_dir_all(data_dir.clone()).unwrap();
-//     // let log_path = data_dir.join(LOG_FILE.clone());
-//     // let log_file = std::fs::File::create(log_path).unwrap();
-//
-//     env_logger::Builder::new()
-//         .target(env_logger::Target::Pipe(Box::new(writer)))
-//         .filter(None, level)
-//         .format(|buf, record| writeln!(buf, "[{}] - {}", record.level(), record.args()))
-//         .init()
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_console_layer_records_events() {
+        let logs: Logs = Default::default();
+        let subscriber = tracing_subscriber::registry().with(ConsoleLayer::new(logs.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(path = "a.rs", "No preview for {}", "a.rs");
+        });
+
+        let logs = logs.lock().unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].level(), Level::WARN);
+        assert_eq!(logs[0].message(), "No preview for a.rs path=\"a.rs\"");
+        assert!(logs[0].target().starts_with("diff_tool"));
+    }
+
+    #[test]
+    fn test_console_layer_capacity() {
+        let logs: Logs = Default::default();
+        let subscriber = tracing_subscriber::registry().with(ConsoleLayer::new(logs.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            for number in 0..CONSOLE_CAPACITY + 5 {
+                tracing::info!("{}", number);
+            }
+            tracing::warn!("last");
+        });
+
+        let console = ConsoleRecords::new(&logs, None);
+        assert_eq!(console.len(), CONSOLE_CAPACITY);
+        assert_eq!(console.iter().next().map(LogRecord::message), Some("6"));
+        let warnings = ConsoleRecords {
+            level: Some(Level::WARN),
+            ..console
+        };
+        assert_eq!(
+            warnings.iter().map(LogRecord::message).collect::<Vec<_>>(),
+            ["last"]
+        );
+    }
+
+    #[test]
+    fn test_rotating_file() -> Result<()> {
+        let root = std::env::temp_dir().join("diff-tool-test-rotating-file");
+        let _ = std::fs::remove_dir_all(&root);
+        let path = root.join("diff-tool.log");
+        std::fs::create_dir_all(&root)?;
+        std::fs::write(&path, "old\n")?;
+
+        // Appends to the existing file
+        let file = RotatingFile::open(path.clone(), 8, 2)?;
+        (&file).write_all(b"one\n")?;
+        let appended = std::fs::read_to_string(&path)?;
+
+        // Then rotates once a line won't fit, keeping two old files
+        for line in ["two\n", "three\n", "four\n"] {
+            (&file).write_all(line.as_bytes())?;
+        }
+        let read = |n: usize| std::fs::read_to_string(file.rotated(n)).unwrap_or_default();
+        let rotated = [std::fs::read_to_string(&path)?, read(1), read(2), read(3)];
+        std::fs::remove_dir_all(&root)?;
+
+        assert_eq!(appended, "old\none\n");
+        assert_eq!(rotated, ["four\n", "three\n", "two\n", ""]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_of_day() {
+        let mut record = LogRecord::new(Level::INFO, "diff_tool", "");
+        record.timestamp = UNIX_EPOCH + Duration::from_secs(3 * 86400 + 13 * 3600 + 5 * 60 + 9);
+        assert_eq!(record.time_of_day(), "13:05:09");
+    }
+}
@@ -1,5 +1,11 @@
 pub mod cli;
 pub mod config;
+pub mod conflict;
+pub mod directory;
+pub mod encoding;
 pub mod git;
+pub mod image;
+pub mod json;
 pub mod logger;
+pub mod source;
 pub mod terminal;
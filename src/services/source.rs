@@ -0,0 +1,395 @@
+use anyhow::{Context, Result};
+use encoding_rs::{Encoding, UTF_8};
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    conflict::ConflictFile,
+    directory::{pair_files, read_entry, FileChange},
+    encoding::{self, is_utf16, Decoded},
+    git::{get_index_content, get_raw_diff, get_repo_root},
+    image::{is_image_path, ImageDiff},
+};
+use crate::diff::{is_binary, Diff, WhitespaceOptions};
+
+/// Where the diff to display comes from
+#[derive(Debug, Clone)]
+pub enum DiffSource {
+    /// Run `git diff` on a file, or on every changed file under a directory
+    Git { path: PathBuf, change_dir: bool },
+    /// Read a unified diff from a file, or from stdin when `None`
+    Patch(Option<PathBuf>),
+    /// Compare two files in-process, `label` is the real path when they are temporary copies and
+    /// `new_label` the new side's path when git reports a rename or copy
+    Files {
+        old: PathBuf,
+        new: PathBuf,
+        label: Option<String>,
+        new_label: Option<String>,
+    },
+    /// Compare every file of two directory trees in-process, pairing them by relative path and
+    /// leaving out anything matching the `ignore` globs
+    Directories {
+        old: PathBuf,
+        new: PathBuf,
+        ignore: Vec<String>,
+    },
+}
+
+/// How a `DiffSource` is read, from the command line
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Overrides the detected text encoding
+    encoding: Option<&'static Encoding>,
+    whitespace: WhitespaceOptions,
+}
+
+impl DiffOptions {
+    pub fn with_encoding(mut self, encoding: Option<&'static Encoding>) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn encoding(&self) -> Option<&'static Encoding> {
+        self.encoding
+    }
+
+    pub fn with_whitespace(mut self, whitespace: WhitespaceOptions) -> Self {
+        self.whitespace = whitespace;
+        self
+    }
+
+    /// Whitespace differences left out of the diff
+    pub fn whitespace(&self) -> &WhitespaceOptions {
+        &self.whitespace
+    }
+}
+
+/// What was loaded from a `DiffSource`
+#[derive(Debug)]
+pub enum Content {
+    Diffs(Vec<Diff>),
+    Conflict(ConflictFile),
+}
+
+impl DiffSource {
+    pub fn load(&self, options: &DiffOptions) -> Result<Content> {
+        match self {
+            DiffSource::Git { path, change_dir } => {
+                let mut raw_diff =
+                    get_raw_diff(path, *change_dir, &options.whitespace().git_args())?;
+                if Diff::is_combined(&String::from_utf8_lossy(&raw_diff)) {
+                    if !path.is_dir() {
+                        return load_conflict(path, options);
+                    }
+                    // Mid-merge, the conflicted files are listed and the rest of the diff shown,
+                    // or the first conflict opened when nothing else changed
+                    let (resolved, conflicted) = split_conflicts(&raw_diff);
+                    if decode_patch(&resolved, options).is_empty() {
+                        if let Some(first) = conflicted.first() {
+                            return load_conflict(&get_repo_root(path)?.join(first), options);
+                        }
+                    }
+                    tracing::warn!(
+                        "{} conflicted, open a file to resolve its conflicts: {}",
+                        conflicted.len(),
+                        conflicted.join(", ")
+                    );
+                    raw_diff = resolved;
+                }
+                let diffs = decode_patch(&raw_diff, options)
+                    .into_iter()
+                    .map(|diff| match diff.binary() {
+                        Some(_) => load_binary(&file_in_repo(path, &diff)?, &diff, options),
+                        None => Ok(diff.ignore_whitespace(options.whitespace())),
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(Content::Diffs(diffs))
+            }
+            DiffSource::Patch(path) => {
+                let patch = match path {
+                    Some(path) => std::fs::read(path)
+                        .with_context(|| format!("Failed to read patch {}", path.display()))?,
+                    None => {
+                        let mut patch = Vec::new();
+                        std::io::stdin()
+                            .read_to_end(&mut patch)
+                            .context("Failed to read patch from stdin")?;
+                        patch
+                    }
+                };
+                let diffs = decode_patch(&patch, options)
+                    .into_iter()
+                    .map(|diff| diff.ignore_whitespace(options.whitespace()))
+                    .collect();
+                Ok(Content::Diffs(diffs))
+            }
+            DiffSource::Files {
+                old,
+                new,
+                label,
+                new_label,
+            } => {
+                let old_content = (!is_dev_null(old)).then(|| read_side(old)).transpose()?;
+                let new_content = (!is_dev_null(new)).then(|| read_side(new)).transpose()?;
+
+                let old_path = (!is_dev_null(old))
+                    .then(|| label.clone().unwrap_or_else(|| old.display().to_string()));
+                let new_path = (!is_dev_null(new)).then(|| {
+                    new_label
+                        .clone()
+                        .or_else(|| label.clone())
+                        .unwrap_or_else(|| new.display().to_string())
+                });
+                let diff =
+                    compare_contents(old_content.as_deref(), new_content.as_deref(), options)
+                        .with_paths(old_path.as_deref(), new_path.as_deref());
+
+                Ok(Content::Diffs(vec![diff]))
+            }
+            DiffSource::Directories { old, new, ignore } => {
+                let pairs = pair_files(old, new, ignore)?;
+                let count = |change| pairs.iter().filter(|pair| pair.change() == change).count();
+                tracing::info!(
+                    "Compared {} with {}: {} changed, {} added, {} removed, {} identical",
+                    old.display(),
+                    new.display(),
+                    count(FileChange::Changed),
+                    count(FileChange::Added),
+                    count(FileChange::Removed),
+                    count(FileChange::Identical)
+                );
+
+                let diffs = pairs
+                    .iter()
+                    .filter(|pair| pair.change() != FileChange::Identical)
+                    .map(|pair| {
+                        let path = pair.path();
+                        let old_content = (pair.change() != FileChange::Added)
+                            .then(|| read_entry(&old.join(path)))
+                            .transpose()?;
+                        let new_content = (pair.change() != FileChange::Removed)
+                            .then(|| read_entry(&new.join(path)))
+                            .transpose()?;
+                        Ok(compare_contents(
+                            old_content.as_deref(),
+                            new_content.as_deref(),
+                            options,
+                        )
+                        .with_paths(
+                            old_content.is_some().then_some(path),
+                            new_content.is_some().then_some(path),
+                        ))
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(Content::Diffs(diffs))
+            }
+        }
+    }
+
+    /// Where to look for the project config from: the directory being diffed, or that of the
+    /// files being compared. `None` for patches, which say nothing about where they apply.
+    pub fn project_dir(&self) -> Option<PathBuf> {
+        let dir_of = |path: &Path| {
+            if path.is_dir() {
+                Some(path.to_path_buf())
+            } else {
+                path.parent().map(Path::to_path_buf)
+            }
+        };
+        match self {
+            DiffSource::Git { path, .. } => dir_of(path),
+            DiffSource::Patch(_) => None,
+            // The new side, as git difftool passes the working tree file as `$REMOTE`
+            DiffSource::Files { old, new, .. } if is_dev_null(new) => dir_of(old),
+            DiffSource::Files { new, .. } => dir_of(new),
+            DiffSource::Directories { new, .. } => Some(new.clone()),
+        }
+    }
+
+    /// Decodes both versions of an image file for the preview, `None` for other files, patches
+    /// (which don't carry the file content) or images that fail to decode
+    pub fn load_image(&self, diff: &Diff) -> Option<ImageDiff> {
+        let path = diff.path().filter(|path| is_image_path(path))?;
+
+        let (old, new) = match self {
+            DiffSource::Git { path, .. } => {
+                let path = file_in_repo(path, diff).ok()?;
+                let path = path.as_path();
+                let old = match diff.old_path() {
+                    Some(_) => get_index_content(path).ok().flatten(),
+                    None => None,
+                };
+                let new = match diff.new_path() {
+                    Some(_) => std::fs::read(path).ok(),
+                    None => None,
+                };
+                (old, new)
+            }
+            DiffSource::Files { old, new, .. } => (
+                (!is_dev_null(old)).then(|| read_side(old).ok()).flatten(),
+                (!is_dev_null(new)).then(|| read_side(new).ok()).flatten(),
+            ),
+            DiffSource::Directories { old, new, .. } => (
+                diff.old_path()
+                    .and_then(|path| std::fs::read(old.join(path)).ok()),
+                diff.new_path()
+                    .and_then(|path| std::fs::read(new.join(path)).ok()),
+            ),
+            DiffSource::Patch(_) => return None,
+        };
+
+        match ImageDiff::decode(path, old.as_deref(), new.as_deref()) {
+            Ok(images) => Some(images),
+            Err(e) => {
+                tracing::warn!("No image preview: {:#}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Reads one side of a file comparison, `/dev/null` stands in for a missing file
+fn read_side(path: &Path) -> Result<Vec<u8>> {
+    if is_dev_null(path) {
+        return Ok(Vec::new());
+    }
+    std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// Compares two versions of a file as text, or as binary when either side looks binary. UTF-16
+/// text looks binary to git's heuristic, so is caught by its BOM or `--encoding` first.
+fn compare_contents(old: Option<&[u8]>, new: Option<&[u8]>, options: &DiffOptions) -> Diff {
+    let is_text = |side: Option<&[u8]>| {
+        side.is_none_or(|bytes| !is_binary(bytes) || is_utf16(bytes, options.encoding()))
+    };
+    if !is_text(old) || !is_text(new) {
+        return Diff::from_binary(old, new);
+    }
+
+    let old = encoding::decode(old.unwrap_or_default(), options.encoding());
+    let new = encoding::decode(new.unwrap_or_default(), options.encoding());
+    let diff = Diff::from_texts_with(old.text(), new.text(), options.whitespace());
+    label_encoding(diff, [&old, &new])
+}
+
+/// Parses a unified diff, decoding each file's section on its own so that files in different
+/// encodings all read correctly and are labelled with their own encoding
+fn decode_patch(patch: &[u8], options: &DiffOptions) -> Vec<Diff> {
+    Diff::split_patch(patch)
+        .into_iter()
+        .flat_map(|section| {
+            let decoded = encoding::decode(section, options.encoding());
+            Diff::parse_patch(decoded.text())
+                .into_iter()
+                .map(|diff| label_encoding(diff, [&decoded]))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Conflicted files are shown from their conflict markers rather than the combined diff
+fn load_conflict(path: &Path, options: &DiffOptions) -> Result<Content> {
+    let content = encoding::decode(&std::fs::read(path)?, options.encoding());
+    Ok(Content::Conflict(ConflictFile::parse(
+        path,
+        content.text(),
+    )?))
+}
+
+/// Separates the combined `diff --cc` sections of a mid-merge `git diff` from the rest, returning
+/// the rest of the diff and the paths of the conflicted files
+fn split_conflicts(raw_diff: &[u8]) -> (Vec<u8>, Vec<String>) {
+    let mut resolved = Vec::new();
+    let mut conflicted = Vec::new();
+    for section in Diff::split_patch(raw_diff) {
+        let text = String::from_utf8_lossy(section);
+        if Diff::is_combined(&text) {
+            conflicted.extend(
+                text.lines()
+                    .find_map(|line| line.strip_prefix("diff --cc "))
+                    .map(str::to_string),
+            );
+        } else {
+            resolved.extend_from_slice(section);
+        }
+    }
+    (resolved, conflicted)
+}
+
+/// Notes the encoding on diffs decoded from something other than UTF-8
+fn label_encoding<const N: usize>(diff: Diff, decoded: [&Decoded; N]) -> Diff {
+    match decoded.iter().find(|decoded| decoded.encoding() != UTF_8) {
+        Some(decoded) => diff.with_encoding(decoded.encoding().name()),
+        None => diff,
+    }
+}
+
+/// Replaces git's "Binary files differ" with a summary of the staged and working tree content,
+/// or a text diff when they turn out to be UTF-16
+fn load_binary(path: &Path, diff: &Diff, options: &DiffOptions) -> Result<Diff> {
+    let old_content = match diff.old_path() {
+        Some(_) => get_index_content(path)?,
+        None => None,
+    };
+    let new_content = match diff.new_path() {
+        Some(_) => std::fs::read(path).ok(),
+        None => None,
+    };
+
+    Ok(
+        compare_contents(old_content.as_deref(), new_content.as_deref(), options)
+            .with_paths(diff.old_path(), diff.new_path()),
+    )
+}
+
+/// Path of a diff's file on disk when `git diff` was run on `path`, which may be a directory
+fn file_in_repo(path: &Path, diff: &Diff) -> Result<PathBuf> {
+    match diff.path() {
+        Some(file) if path.is_dir() => Ok(get_repo_root(path)?.join(file)),
+        _ => Ok(path.to_path_buf()),
+    }
+}
+
+fn is_dev_null(path: &Path) -> bool {
+    path == Path::new("/dev/null") || path == Path::new("nul")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_conflicts() {
+        let raw_diff = b"diff --cc conflicted.txt\nindex 1111111,2222222..0000000\n--- a/conflicted.txt\n+++ b/conflicted.txt\n@@@ -1,1 -1,1 +1,5 @@@\n++<<<<<<< HEAD\n +ours\n++=======\n+ theirs\n++>>>>>>> branch\ndiff --git a/merged.txt b/merged.txt\n--- a/merged.txt\n+++ b/merged.txt\n@@ -1 +1 @@\n-old\n+new\n";
+
+        let (resolved, conflicted) = split_conflicts(raw_diff);
+        assert_eq!(conflicted, ["conflicted.txt"]);
+        let diffs = decode_patch(&resolved, &DiffOptions::default());
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path(), Some("merged.txt"));
+    }
+
+    #[test]
+    fn test_patch_files_decoded_separately() {
+        let mut patch = b"diff --git a/latin1.txt b/latin1.txt\n--- a/latin1.txt\n+++ b/latin1.txt\n@@ -1 +1 @@\n-caf\xe9 cr\xe8me\n+cr\xe8me br\xfbl\xe9e \xe0 la fran\xe7aise\n".to_vec();
+        patch.extend_from_slice(
+            "diff --git a/utf8.txt b/utf8.txt\n--- a/utf8.txt\n+++ b/utf8.txt\n@@ -1 +1 @@\n-naïve\n+naïveté\n".as_bytes(),
+        );
+        let path = std::env::temp_dir().join("diff-tool-test-mixed-encodings.patch");
+        std::fs::write(&path, patch).unwrap();
+
+        let content = DiffSource::Patch(Some(path.clone())).load(&DiffOptions::default());
+        std::fs::remove_file(&path).unwrap();
+
+        let Ok(Content::Diffs(diffs)) = content else {
+            panic!("expected diffs");
+        };
+        assert_eq!(diffs[0].encoding(), Some("windows-1252"));
+        assert_eq!(diffs[0].old_diff()[0].content(), "café crème");
+        assert_eq!(diffs[1].encoding(), None);
+        assert_eq!(diffs[1].current_diff()[0].content(), "naïveté");
+    }
+}
@@ -1,6 +1,10 @@
-use std::fmt::{self, Display, Formatter};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
 
 use crossterm::event;
+use thiserror::Error;
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
 pub enum Key {
@@ -149,12 +153,13 @@ impl From<event::KeyEvent> for Key {
                 state: _,
             } => Key::Ctrl(c),
 
+            // Terminals report shift for symbols like `?`, which are bound as typed
             event::KeyEvent {
                 code: event::KeyCode::Char(c),
                 modifiers: event::KeyModifiers::SHIFT,
                 kind: _,
                 state: _,
-            } => Key::Shift(c),
+            } if c.is_alphabetic() => Key::Shift(c),
 
             event::KeyEvent {
                 code: event::KeyCode::Char(c),
@@ -166,6 +171,77 @@ impl From<event::KeyEvent> for Key {
     }
 }
 
+/// Why a key in the config couldn't be read
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KeyParseError {
+    #[error("empty key")]
+    Empty,
+    #[error("unknown key '{0}'")]
+    UnknownKey(String),
+    #[error("unknown modifier '{0}', expected ctrl, alt or shift")]
+    UnknownModifier(String),
+}
+
+/// Reads a key as written in the keymap, e.g. `j`, `G`, `Enter`, `ctrl+c` or `Shift+g`.
+/// Modifiers and key names are case-insensitive, and an uppercase letter is the same as
+/// `shift+` the letter.
+impl FromStr for Key {
+    type Err = KeyParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => return Err(KeyParseError::Empty),
+            (Some(c), None) if c.is_uppercase() => return Ok(Key::Shift(c)),
+            (Some(c), None) => return Ok(Key::Char(c)),
+            _ => {}
+        }
+
+        if let Some((modifier, key)) = s.split_once('+').filter(|(_, key)| !key.is_empty()) {
+            let c = match key.to_lowercase().as_str() {
+                "space" => ' ',
+                _ => {
+                    let mut chars = key.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => c,
+                        _ => return Err(KeyParseError::UnknownKey(s.to_string())),
+                    }
+                }
+            };
+            return match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => Ok(Key::Ctrl(c.to_ascii_lowercase())),
+                "alt" => Ok(Key::Alt(c)),
+                "shift" if c.is_alphabetic() => Ok(Key::Shift(c)),
+                "shift" => Ok(Key::Char(c)),
+                _ => Err(KeyParseError::UnknownModifier(modifier.to_string())),
+            };
+        }
+
+        let key = match s.to_lowercase().as_str() {
+            "enter" | "return" => Key::Enter,
+            "tab" => Key::Tab,
+            "backspace" => Key::Backspace,
+            "esc" | "escape" => Key::Esc,
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "ins" | "insert" => Key::Ins,
+            "del" | "delete" => Key::Delete,
+            "home" => Key::Home,
+            "end" => Key::End,
+            "pageup" => Key::PageUp,
+            "pagedown" => Key::PageDown,
+            "space" => Key::Char(' '),
+            name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n @ 0..=12) => Key::from_f(n),
+                _ => return Err(KeyParseError::UnknownKey(s.to_string())),
+            },
+        };
+        Ok(key)
+    }
+}
+
 impl Display for Key {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match *self {
@@ -180,3 +256,32 @@ impl Display for Key {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!("j".parse(), Ok(Key::Char('j')));
+        assert_eq!("G".parse(), Ok(Key::Shift('G')));
+        assert_eq!("Shift+g".parse(), Ok(Key::Shift('g')));
+        assert_eq!("CTRL+C".parse(), Ok(Key::Ctrl('c')));
+        assert_eq!("esc".parse(), Ok(Key::Esc));
+        assert_eq!("PageDown".parse(), Ok(Key::PageDown));
+        assert_eq!("f5".parse(), Ok(Key::F5));
+        assert_eq!("+".parse(), Ok(Key::Char('+')));
+        assert_eq!(
+            "hyper+x".parse::<Key>(),
+            Err(KeyParseError::UnknownModifier("hyper".to_string()))
+        );
+        assert_eq!(
+            "enterr".parse::<Key>(),
+            Err(KeyParseError::UnknownKey("enterr".to_string()))
+        );
+
+        // Keys are matched by how they display, whatever case they were written in
+        let keys = ["Shift+g", "G", "shift+G"].map(|key| key.parse::<Key>().unwrap().to_string());
+        assert!(keys.iter().all(|key| key == "shift+g"));
+    }
+}
@@ -9,6 +9,122 @@ pub enum Message {
     NextRow,
     FirstRow,
     LastRow,
+    NextFile,
+    PrevFile,
+    NextConflict,
+    PrevConflict,
+    PickOurs,
+    PickTheirs,
+    PickBoth,
+    WriteResolved,
+    ToggleImagePreview,
+    ToggleWhitespace,
+    GoToMoved,
+    ToggleFileTree,
+    SwitchFocus,
+    OpenFile,
+    OpenPalette,
+    ToggleHelp,
+    CycleLogLevel,
+    /// Runs the item selected in the command palette, sent by the palette rather than bound
+    #[serde(skip_deserializing)]
+    ConfirmPalette,
+}
+
+impl Message {
+    /// Every message a user can run, in the order the command palette lists them
+    pub const ACTIONS: [Message; 22] = [
+        Message::NextRow,
+        Message::PrevRow,
+        Message::FirstRow,
+        Message::LastRow,
+        Message::NextFile,
+        Message::PrevFile,
+        Message::ToggleFileTree,
+        Message::SwitchFocus,
+        Message::OpenFile,
+        Message::GoToMoved,
+        Message::NextConflict,
+        Message::PrevConflict,
+        Message::PickOurs,
+        Message::PickTheirs,
+        Message::PickBoth,
+        Message::WriteResolved,
+        Message::ToggleWhitespace,
+        Message::ToggleImagePreview,
+        Message::OpenPalette,
+        Message::ToggleHelp,
+        Message::CycleLogLevel,
+        Message::Quit,
+    ];
+
+    /// Whether a count typed before the message repeats it, e.g. `3j`. Only motions repeat,
+    /// other actions ignore the count so `3q` or a count before a toggle run once.
+    pub fn repeatable(self) -> bool {
+        matches!(
+            self,
+            Message::PrevRow
+                | Message::NextRow
+                | Message::NextFile
+                | Message::PrevFile
+                | Message::NextConflict
+                | Message::PrevConflict
+        )
+    }
+
+    /// The group the message is listed under in the help
+    pub fn category(self) -> Category {
+        match self {
+            Message::PrevRow
+            | Message::NextRow
+            | Message::FirstRow
+            | Message::LastRow
+            | Message::NextFile
+            | Message::PrevFile
+            | Message::SwitchFocus
+            | Message::OpenFile
+            | Message::GoToMoved => Category::Navigation,
+            Message::OpenPalette | Message::ConfirmPalette => Category::Search,
+            Message::NextConflict
+            | Message::PrevConflict
+            | Message::PickOurs
+            | Message::PickTheirs
+            | Message::PickBoth
+            | Message::WriteResolved => Category::Conflicts,
+            Message::ToggleImagePreview
+            | Message::ToggleWhitespace
+            | Message::ToggleFileTree
+            | Message::ToggleHelp
+            | Message::CycleLogLevel => Category::View,
+            Message::Quit => Category::General,
+        }
+    }
+}
+
+/// Groups of messages in the help
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub enum Category {
+    Navigation,
+    Search,
+    Conflicts,
+    View,
+    General,
+}
+
+impl Category {
+    pub const ALL: [Category; 5] = [
+        Category::Navigation,
+        Category::Search,
+        Category::Conflicts,
+        Category::View,
+        Category::General,
+    ];
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
 }
 
 /// Display a user friendly short description of action
@@ -20,6 +136,24 @@ impl Display for Message {
             Message::LastRow => "Jump to bottom row",
             Message::FirstRow => "Jump to top row",
             Message::Quit => "Quit application",
+            Message::NextFile => "Show next file",
+            Message::PrevFile => "Show previous file",
+            Message::NextConflict => "Jump to next conflict",
+            Message::PrevConflict => "Jump to previous conflict",
+            Message::PickOurs => "Resolve conflict with ours",
+            Message::PickTheirs => "Resolve conflict with theirs",
+            Message::PickBoth => "Resolve conflict with both",
+            Message::WriteResolved => "Write resolved file",
+            Message::ToggleImagePreview => "Toggle image preview",
+            Message::ToggleWhitespace => "Toggle visible whitespace",
+            Message::GoToMoved => "Jump to the other end of a moved block",
+            Message::ToggleFileTree => "Show or hide the file tree",
+            Message::SwitchFocus => "Switch focus between the file tree, the diff and the console",
+            Message::OpenFile => "Open the selected file or folder",
+            Message::OpenPalette => "Search actions and files",
+            Message::ToggleHelp => "Show or hide the help",
+            Message::CycleLogLevel => "Change the lowest log level shown in the console",
+            Message::ConfirmPalette => "Run the selected command",
         };
         write!(f, "{}", str)
     }
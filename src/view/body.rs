@@ -1,103 +1,98 @@
 use ratatui::{
+    buffer::Buffer,
     layout::{Constraint, Layout, Rect},
-    style::{Color, Modifier, Style},
-    text::{Line, Span},
-    widgets::{Block, BorderType, Row, Table},
+    widgets::StatefulWidget,
     Frame,
 };
 
+use super::{
+    image::render_image_diff,
+    tree::{render_tree, split},
+};
 use crate::{
     app::App,
-    services::git::{DiffKind, DiffLine},
+    services::conflict::ConflictFile,
+    widget::{pane_table, PaneOptions, SideBySideDiff, SideBySideDiffState},
 };
 
 pub(super) fn render_body(model: &mut App, f: &mut Frame, area: Rect) {
-    // Body Layout (Left Diff & Right Diff)
-    let [left_side, right_side] =
-        Layout::horizontal(Constraint::from_percentages([50, 50])).areas(area);
+    let (tree_area, area) = split(model, area);
+    if let Some(tree_area) = tree_area {
+        render_tree(model, tree_area, f.buffer_mut());
+    }
 
-    let line_number_char_len = model.diff().unwrap().largest_line_number_char_len();
+    let mut state = model.diff_state().borrow_mut();
 
-    // Old/Left Diff
-    let old_diff = model.diff().unwrap().old_diff();
-    let old_diff_table = build_diff_table(old_diff, false, line_number_char_len);
-    let mut old_diff_state = model.diff_state().old_diff().borrow_mut();
+    if let Some(conflict) = model.conflict() {
+        let options = PaneOptions {
+            visible_whitespace: model.show_whitespace(),
+            ..Default::default()
+        };
+        render_conflict(conflict, area, f.buffer_mut(), &mut state, &options);
+        return;
+    }
 
-    // Current/Right Diff
-    let current_diff = model.diff().unwrap().current_diff();
-    let current_diff_table = build_diff_table(current_diff, true, line_number_char_len);
-    let mut current_diff_state = model.diff_state().current_diff().borrow_mut();
+    let diff = model.diff().unwrap();
+    if let Some(images) = model.image_preview(diff) {
+        render_image_diff(
+            diff.path().unwrap_or_default(),
+            images,
+            area,
+            f.buffer_mut(),
+        );
+        return;
+    }
 
-    f.render_stateful_widget(old_diff_table, left_side, &mut old_diff_state);
-    f.render_stateful_widget(current_diff_table, right_side, &mut current_diff_state)
+    // TODO: The theme should be a property of the model
+    let widget = SideBySideDiff::new(diff).visible_whitespace(model.show_whitespace());
+    f.render_stateful_widget(widget, area, &mut state)
 }
 
-/// Draws a diff table
-fn build_diff_table(diff: &[DiffLine], is_current_diff: bool, line_number_char_len: u16) -> Table {
-    let diff_title = if is_current_diff { "New" } else { "Original" };
-
-    let rows = diff.iter().map(parse_diff_line);
-
-    // Dynamic column width
-    let widths = [
-        // Line Number col depends on the largest line number
-        Constraint::Length(line_number_char_len),
-        Constraint::Percentage(2),
-        Constraint::Percentage(97),
-    ];
+/// Renders the base, ours and theirs panes of a conflicted file, sharing one selection
+pub(super) fn render_conflict(
+    conflict: &ConflictFile,
+    area: Rect,
+    buf: &mut Buffer,
+    state: &mut SideBySideDiffState,
+    options: &PaneOptions,
+) {
+    let [base_side, ours_side, theirs_side] =
+        Layout::horizontal(Constraint::from_ratios([(1, 3), (1, 3), (1, 3)])).areas(area);
 
-    Table::new(rows, widths)
-        .block(
-            Block::bordered()
-                .title(Span::styled(
-                    diff_title,
-                    Style::default().fg(Color::LightCyan),
-                ))
-                .style(Style::default().fg(Color::White))
-                .border_type(BorderType::Plain),
-        )
-        .highlight_style(if is_current_diff {
-            Style::default()
-                .fg(Color::Magenta)
-                .add_modifier(Modifier::BOLD)
-        } else {
-            Style::default()
-        })
-        .highlight_symbol(">>")
-}
-
-fn parse_diff_line(line: &DiffLine) -> Row {
-    // TODO: The styling should be a property of the model
-    let line_number_style = Style::default().fg(Color::Gray);
+    let rows = conflict.rows();
+    let largest_line_number = rows.len().to_string().len();
+    let line_number_char_len = largest_line_number.try_into().unwrap_or(4);
 
-    let (prefix_style, content_style) = match line.kind() {
-        DiffKind::Addition => (
-            Style::default()
-                .fg(Color::Green)
-                .add_modifier(Modifier::BOLD),
-            Style::default()
-                .bg(Color::Rgb(131, 242, 140))
-                .fg(Color::Black),
-        ),
-        DiffKind::Removal => (
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-            Style::default().bg(Color::LightRed).fg(Color::Black),
-        ),
-        DiffKind::Neutral => (Style::default(), Style::default()),
-        DiffKind::Blank => (Style::default(), Style::default().bg(Color::DarkGray)),
+    // Titled with the labels of the conflict being looked at, they can differ between conflicts
+    let selected_conflict = conflict.conflict_near_row(state.selected().unwrap_or_default());
+    let ours_title = match selected_conflict.map(|c| c.ours_label()) {
+        Some(label) if !label.is_empty() => format!("Ours ({})", label),
+        _ => "Ours".to_string(),
     };
-
-    let line_number = match line.line_number() {
-        Some(x) => x.to_string(),
-        None => " ".to_string(),
+    let theirs_title = match selected_conflict.map(|c| c.theirs_label()) {
+        Some(label) if !label.is_empty() => format!("Theirs ({})", label),
+        _ => "Theirs".to_string(),
     };
 
-    let prefix = line.kind().value();
-    let content = line.content();
+    let base_table = pane_table(
+        rows.base(),
+        "Base".to_string(),
+        line_number_char_len,
+        false,
+        options,
+    );
+    let ours_table = pane_table(rows.ours(), ours_title, line_number_char_len, true, options);
+    let theirs_table = pane_table(
+        rows.theirs(),
+        theirs_title,
+        line_number_char_len,
+        true,
+        options,
+    );
 
-    Row::new([
-        Line::styled(line_number, line_number_style).right_aligned(),
-        Line::styled(prefix, prefix_style).centered(),
-        Line::styled(content, content_style),
-    ])
+    let mut table_state = state.table_state();
+    StatefulWidget::render(base_table, base_side, buf, &mut table_state.clone());
+    StatefulWidget::render(theirs_table, theirs_side, buf, &mut table_state.clone());
+    StatefulWidget::render(ours_table, ours_side, buf, &mut table_state);
+    state.set_offset(table_state.offset());
 }
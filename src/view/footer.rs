@@ -3,16 +3,26 @@ use std::collections::{HashMap, HashSet};
 use ratatui::{
     layout::{Constraint, Layout, Rect},
     style::{Color, Style},
-    text::Line,
+    text::{Line, Span},
     widgets::{Block, BorderType, List, ListDirection, Row, Table},
     Frame,
 };
 
-use crate::{app::App, update::message::Message};
+use tracing::Level;
+
+use crate::{
+    app::{state::Focus, App},
+    update::message::Message,
+};
 
 pub(super) fn render_footer(app: &App, area: Rect, f: &mut Frame) {
-    // Footer Layout (Console & Help)
-    let [left, right] = Layout::horizontal(Constraint::from_percentages([50, 50])).areas(area);
+    // Footer Layout (Console & Help), the console takes the whole footer without the help
+    let footer_help = app.config().options().footer_help();
+    let [left, right] = if footer_help {
+        Layout::horizontal(Constraint::from_percentages([50, 50])).areas(area)
+    } else {
+        [area, Rect::default()]
+    };
 
     // Console Section
     let console = draw_console(app);
@@ -20,23 +30,60 @@ pub(super) fn render_footer(app: &App, area: Rect, f: &mut Frame) {
     f.render_stateful_widget(console, left, &mut console_state);
 
     // Help Menu
-    let help_menu = build_help_table(app);
-    f.render_widget(help_menu, right);
+    if footer_help {
+        let help_menu = build_help_table(app);
+        f.render_widget(help_menu, right);
+    }
 }
 
-/// Draws the console menu component
-fn draw_console(app: &App) -> List {
-    let items = app.console().to_owned();
+/// Draws the console menu component, each record coloured by its level
+fn draw_console(app: &App) -> List<'_> {
+    let console = app.console();
+    let items: Vec<Line> = console
+        .iter()
+        .map(|record| {
+            Line::from(vec![
+                Span::styled(record.time_of_day(), Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    format!(" {:<5} ", record.level()),
+                    level_style(record.level()),
+                ),
+                Span::raw(record.message().trim_end().to_string()),
+            ])
+        })
+        .collect();
+    drop(console);
+
+    let title = match app.console_level() {
+        Some(level) => format!("Console ({} and above)", level),
+        None => "Console".to_string(),
+    };
+    let border_style = match app.focus() {
+        Focus::Console => Style::default().fg(Color::LightCyan),
+        _ => Style::default(),
+    };
+
     List::new(items)
-        .block(Block::bordered().title("Console"))
+        .block(Block::bordered().title(title).border_style(border_style))
         .style(Style::default().fg(Color::White))
         .highlight_symbol(">>")
         .repeat_highlight_symbol(true)
         .direction(ListDirection::TopToBottom)
 }
 
+fn level_style(level: Level) -> Style {
+    let color = match level {
+        Level::ERROR => Color::Red,
+        Level::WARN => Color::Yellow,
+        Level::INFO => Color::Cyan,
+        Level::DEBUG => Color::Blue,
+        Level::TRACE => Color::Gray,
+    };
+    Style::default().fg(color)
+}
+
 /// Draws the help menu component
-fn build_help_table(app: &App) -> Table {
+fn build_help_table(app: &App) -> Table<'_> {
     let key_style = Style::default().fg(Color::LightCyan);
     let message_style = Style::default().fg(Color::Gray);
 
@@ -76,7 +123,7 @@ fn combine_keys_by_value(map: &HashMap<String, Message>) -> Vec<(String, &Messag
     for (_, message) in map.iter() {
         if !processed_messages.contains(message) {
             let combined_keys = map
-                .into_iter()
+                .iter()
                 .filter(|(_, m)| *m == message)
                 .enumerate()
                 .map(|(i, (k, _))| {
@@ -1,23 +1,146 @@
 use ratatui::{
     layout::Rect,
     style::{Color, Style},
+    text::{Line, Span},
     widgets::{Block, BorderType, Paragraph},
     Frame,
 };
 
-pub(super) fn render_header(f: &mut Frame, area: Rect) {
-    let title = draw_title();
+use crate::app::App;
+
+pub(super) fn render_header(app: &App, f: &mut Frame, area: Rect) {
+    let title = draw_title(app);
     f.render_widget(title, area);
 }
 
-/// Draws the title component
-fn draw_title<'a>() -> Paragraph<'a> {
-    Paragraph::new("Git Diff View")
-        .style(Style::default().fg(Color::LightCyan))
-        .centered()
-        .block(
-            Block::bordered()
-                .style(Style::default().fg(Color::White))
-                .border_type(BorderType::Plain),
-        )
+/// Draws the status line: the file, the revisions compared, its line counts, the hunk under
+/// the cursor and any keys typed towards a binding, e.g.
+/// `src/app.rs (2/5)  1a2b3c4 → 5d6e7f8  +12 -3  hunk 3/7  10g`
+fn draw_title<'a>(app: &App) -> Paragraph<'a> {
+    let path_style = Style::default().fg(Color::LightCyan);
+    let detail_style = Style::default().fg(Color::Gray);
+
+    let Some(diff) = app.diff() else {
+        let title = match app.conflict() {
+            Some(conflict) => format!("Git Diff View - {}", conflict.path().display()),
+            None => "Git Diff View".to_string(),
+        };
+        return header(Line::styled(title, path_style));
+    };
+
+    let mut spans = vec![Span::styled(
+        diff.path().unwrap_or_default().to_string(),
+        path_style,
+    )];
+    if app.diffs().len() > 1 {
+        spans.push(Span::styled(
+            format!(" ({}/{})", app.selected_file() + 1, app.diffs().len()),
+            detail_style,
+        ));
+    }
+    if let (Some(old), Some(new)) = (diff.old_revision(), diff.new_revision()) {
+        spans.push(Span::styled(format!("  {} → {}", old, new), detail_style));
+    }
+
+    spans.push(Span::styled(
+        format!("  +{}", diff.additions()),
+        Style::default().fg(Color::Green),
+    ));
+    spans.push(Span::styled(
+        format!(" -{}", diff.removals()),
+        Style::default().fg(Color::Red),
+    ));
+
+    let hunks = diff.hunks().len();
+    if hunks > 0 {
+        let row = app.diff_state().borrow().selected().unwrap_or(0);
+        let hunk = diff.hunk_at(row).map_or(0, |index| index + 1);
+        spans.push(Span::styled(
+            format!("  hunk {}/{}", hunk, hunks),
+            detail_style,
+        ));
+    }
+    if let Some(encoding) = diff.encoding() {
+        spans.push(Span::styled(format!("  [{}]", encoding), detail_style));
+    }
+    if !app.pending_keys().is_empty() {
+        spans.push(Span::styled(
+            format!("  {}", app.pending_keys()),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+
+    header(Line::from(spans))
+}
+
+fn header<'a>(title: Line<'a>) -> Paragraph<'a> {
+    Paragraph::new(title).centered().block(
+        Block::bordered()
+            .style(Style::default().fg(Color::White))
+            .border_type(BorderType::Plain),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff::Diff, services::config::AppConfig, update::message::Message};
+    use ratatui::{buffer::Buffer, widgets::Widget};
+
+    const RENAMED: &str = "\
+diff --git a/old.rs b/new.rs
+similarity index 90%
+rename from old.rs
+rename to new.rs
+index 1111111..2222222 100644
+--- a/old.rs
++++ b/new.rs
+@@ -1,2 +1,2 @@
+ a
+-b
++c
+@@ -10,2 +10,3 @@
+ j
++k
+ l
+diff --git a/other.rs b/other.rs
+--- a/other.rs
++++ b/other.rs
+@@ -1 +1 @@
+-x
++y
+";
+
+    /// The status line as drawn, without the border and centring
+    fn status_line(app: &App) -> String {
+        let area = Rect::new(0, 0, 80, 3);
+        let mut buf = Buffer::empty(area);
+        draw_title(app).render(area, &mut buf);
+        (1..area.width - 1)
+            .map(|x| buf.get(x, 1).symbol())
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+
+    #[test]
+    fn test_status_line() {
+        let mut app = App::new(Default::default(), AppConfig::defaults().unwrap());
+        let mut diffs = Diff::parse_patch(RENAMED).into_iter();
+        let renamed = diffs.next().unwrap().with_encoding("windows-1252");
+        app.set_diffs([renamed].into_iter().chain(diffs).collect());
+
+        assert_eq!(
+            status_line(&app),
+            "new.rs (1/2)  1111111 → 2222222  +2 -1  hunk 1/2  [windows-1252]"
+        );
+
+        // Down into the second hunk
+        app.update(Message::NextRow);
+        app.update(Message::NextRow);
+        assert_eq!(
+            status_line(&app),
+            "new.rs (1/2)  1111111 → 2222222  +2 -1  hunk 2/2  [windows-1252]"
+        );
+    }
 }
@@ -0,0 +1,119 @@
+use ratatui::{
+    layout::Margin,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    app::App,
+    services::config::KeyMap,
+    update::message::{Category, Message},
+    widget::Theme,
+};
+
+/// Draws every action with the keys bound to it, grouped by category, over the whole screen
+pub(super) fn render_help(model: &mut App, f: &mut Frame) {
+    // TODO: The theme should be a property of the model
+    let theme = Theme::default();
+    let area = f.size().inner(&Margin::new(2, 1));
+    f.render_widget(Clear, area);
+
+    let lines = help_lines(model.config().keymap());
+    let block = Block::bordered()
+        .title(Span::styled("Help", theme.title))
+        .title_bottom(" j/k scroll, esc closes ")
+        .border_type(BorderType::Plain)
+        .style(theme.title);
+
+    let visible = block.inner(area).height as usize;
+    model.clamp_help_scroll(lines.len().saturating_sub(visible));
+    let scroll = model.help_scroll() as u16;
+
+    f.render_widget(Paragraph::new(lines).block(block).scroll((scroll, 0)), area);
+}
+
+/// A heading per category, then a line per action with its keys, or `unbound`
+fn help_lines(keymap: &KeyMap) -> Vec<Line<'static>> {
+    let heading_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let key_style = Style::default().fg(Color::LightCyan);
+    let unbound_style = Style::default().fg(Color::DarkGray);
+    let message_style = Style::default().fg(Color::Gray);
+
+    let actions: Vec<(Message, String)> = Message::ACTIONS
+        .into_iter()
+        .map(|message| (message, bound_keys(keymap, message)))
+        .collect();
+    let width = actions
+        .iter()
+        .map(|(_, keys)| keys.chars().count())
+        .max()
+        .unwrap_or_default()
+        .max("unbound".len());
+
+    let mut lines = Vec::new();
+    for category in Category::ALL {
+        if !lines.is_empty() {
+            lines.push(Line::default());
+        }
+        lines.push(Line::styled(category.to_string(), heading_style));
+        for (message, keys) in actions.iter().filter(|(m, _)| m.category() == category) {
+            let keys = if keys.is_empty() {
+                Span::styled(format!("  {:width$}", "unbound"), unbound_style)
+            } else {
+                Span::styled(format!("  {:width$}", keys), key_style)
+            };
+            lines.push(Line::from(vec![
+                keys,
+                Span::styled(format!("  {}", message), message_style),
+            ]));
+        }
+    }
+    lines
+}
+
+/// The keys bound to `message`, e.g. `ctrl+c, q`
+pub(super) fn bound_keys(keymap: &KeyMap, message: Message) -> String {
+    let mut keys: Vec<&str> = keymap
+        .iter()
+        .filter(|(_, bound)| **bound == message)
+        .map(|(keys, _)| keys.as_str())
+        .collect();
+    keys.sort_unstable();
+    keys.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::config::AppConfig;
+
+    #[test]
+    fn test_help_lines() {
+        let keymap = AppConfig::defaults().unwrap().keymap().clone();
+        let lines: Vec<String> = help_lines(&keymap)
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect()
+            })
+            .collect();
+
+        assert_eq!(lines[0], "Navigation");
+        assert!(lines[1].starts_with("  j "));
+        assert!(lines[1].ends_with("  Scroll down one row"));
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("  :, ctrl+p")
+                && line.ends_with("Search actions and files")));
+        assert_eq!(
+            lines.iter().filter(|line| !line.starts_with(' ')).count(),
+            Category::ALL.len() * 2 - 1
+        );
+    }
+}
@@ -0,0 +1,354 @@
+use ratatui::style::{Color, Modifier, Style};
+use std::fmt::Write;
+
+use crate::{
+    app::App,
+    diff::{Diff, DiffKind, DiffLine},
+    widget::Theme,
+};
+
+/// Renders the app's diffs as a self-contained HTML page with a side by side table per file,
+/// styled with the theme's colours
+pub fn render_html(app: &App, theme: &Theme) -> String {
+    let mut sections = Vec::new();
+
+    if let Some(conflict) = app.conflict() {
+        let rows = conflict.rows();
+        let title = conflict.path().display().to_string();
+        sections.push((
+            title,
+            vec![
+                ("Base".to_string(), rows.base()),
+                ("Ours".to_string(), rows.ours()),
+                ("Theirs".to_string(), rows.theirs()),
+            ],
+            None,
+        ));
+    }
+
+    for diff in app.diffs() {
+        let title = diff.path().unwrap_or("diff").to_string();
+        sections.push((
+            title,
+            vec![
+                (side_title("Original", diff.old_path()), diff.old_diff()),
+                (new_side_title(diff), diff.current_diff()),
+            ],
+            Some(diff),
+        ));
+    }
+
+    let page_title = match sections.as_slice() {
+        [(title, ..)] => escape(title),
+        sections => format!("{} files", sections.len()),
+    };
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Diff - {}</title>\n<style>\n{}</style>\n</head>\n<body>\n",
+        page_title,
+        stylesheet(theme)
+    );
+
+    // File index
+    if sections.len() > 1 {
+        html.push_str("<nav>\n<h1>Files</h1>\n<ul>\n");
+        for (index, (title, _, diff)) in sections.iter().enumerate() {
+            let stats = diff
+                .map(|diff| {
+                    format!(
+                        " <span class=\"additions\">+{}</span> <span class=\"removals\">-{}</span>",
+                        diff.additions(),
+                        diff.removals()
+                    )
+                })
+                .unwrap_or_default();
+            let _ = writeln!(
+                html,
+                "<li><a href=\"#file-{}\">{}</a>{}</li>",
+                index,
+                escape(title),
+                stats
+            );
+        }
+        html.push_str("</ul>\n</nav>\n");
+    }
+
+    for (index, (title, panes, diff)) in sections.iter().enumerate() {
+        let _ = writeln!(
+            html,
+            "<section id=\"file-{}\">\n<h2>{}</h2>",
+            index,
+            escape(title)
+        );
+        if let Some(summary) = diff.and_then(|diff| diff.binary()) {
+            let _ = writeln!(
+                html,
+                "<p class=\"binary\">{}</p>",
+                escape(&summary.to_string())
+            );
+        }
+        html.push_str(&render_table(panes));
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn side_title(side: &str, path: Option<&str>) -> String {
+    match path {
+        Some(path) => format!("{}: {}", side, path),
+        None => side.to_string(),
+    }
+}
+
+/// Like the viewer, the new side's title also carries renames and mode changes
+fn new_side_title(diff: &Diff) -> String {
+    let title = side_title("New", diff.new_path());
+    match diff.change_description() {
+        Some(change) => format!("{} ({})", title, change),
+        None => title,
+    }
+}
+
+/// One table row per aligned line, each pane taking a line number, prefix and content column
+fn render_table(panes: &[(String, &[DiffLine])]) -> String {
+    let mut html = String::from("<table class=\"diff\">\n<thead><tr>");
+    for (title, _) in panes {
+        let _ = write!(html, "<th colspan=\"3\">{}</th>", escape(title));
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+
+    let rows = panes
+        .iter()
+        .map(|(_, lines)| lines.len())
+        .max()
+        .unwrap_or(0);
+    for row in 0..rows {
+        html.push_str("<tr>");
+        for (_, lines) in panes {
+            match lines.get(row) {
+                Some(line) => render_line(&mut html, line),
+                None => html.push_str("<td class=\"ln\"></td><td></td><td class=\"blank\"></td>"),
+            }
+        }
+        html.push_str("</tr>\n");
+    }
+
+    html.push_str("</tbody>\n</table>\n");
+    html
+}
+
+fn render_line(html: &mut String, line: &DiffLine) {
+    let kind = kind_class(line);
+    let line_number = line
+        .line_number()
+        .map(|number| number.to_string())
+        .unwrap_or_default();
+
+    let _ = write!(
+        html,
+        "<td class=\"ln\">{}</td><td class=\"prefix {}-prefix\">{}</td><td class=\"{}\">",
+        line_number,
+        kind,
+        escape(line.kind().value()),
+        kind
+    );
+
+    let content = line.content();
+    let mut offset = 0;
+    for range in line.highlights() {
+        html.push_str(&escape(&content[offset..range.start]));
+        let _ = write!(
+            html,
+            "<span class=\"{}-highlight\">{}</span>",
+            kind,
+            escape(&content[range.clone()])
+        );
+        offset = range.end;
+    }
+    html.push_str(&escape(&content[offset..]));
+    html.push_str("</td>");
+}
+
+fn kind_class(line: &DiffLine) -> &'static str {
+    match line.kind() {
+        DiffKind::Addition => "addition",
+        DiffKind::Removal => "removal",
+        DiffKind::Neutral => "neutral",
+        DiffKind::Conflict => "conflict",
+        DiffKind::Moved => "moved",
+        DiffKind::Blank => "blank",
+    }
+}
+
+fn stylesheet(theme: &Theme) -> String {
+    let rules = [
+        ("th", theme.title),
+        (".ln", theme.line_number),
+        (".addition-prefix", theme.addition_prefix),
+        (".addition", theme.addition),
+        (".addition-highlight", theme.addition_highlight),
+        (".removal-prefix", theme.removal_prefix),
+        (".removal", theme.removal),
+        (".removal-highlight", theme.removal_highlight),
+        (".neutral", theme.neutral),
+        (".conflict-prefix", theme.conflict_prefix),
+        (".conflict", theme.conflict),
+        (".moved-prefix", theme.moved_prefix),
+        (".moved", theme.moved),
+        (".blank", theme.blank),
+        (".additions", theme.addition_prefix),
+        (".removals", theme.removal_prefix),
+    ];
+
+    let mut css = String::from(
+        "body { background: #1e1e1e; color: #ffffff; font-family: sans-serif; }\n\
+         a { color: inherit; }\n\
+         table.diff { border-collapse: collapse; width: 100%; table-layout: fixed; \
+         font-family: monospace; }\n\
+         table.diff td { white-space: pre-wrap; word-break: break-all; vertical-align: top; \
+         padding: 0 4px; }\n\
+         table.diff th { text-align: left; padding: 4px; }\n\
+         .ln { text-align: right; width: 4em; }\n\
+         .prefix { text-align: center; width: 1.5em; }\n",
+    );
+
+    for (selector, style) in rules {
+        let declarations = style_to_css(style);
+        if !declarations.is_empty() {
+            let _ = writeln!(css, "{} {{ {}}}", selector, declarations);
+        }
+    }
+
+    css
+}
+
+fn style_to_css(style: Style) -> String {
+    let mut css = String::new();
+
+    if let Some(fg) = style.fg.and_then(color_to_css) {
+        let _ = write!(css, "color: {}; ", fg);
+    }
+    if let Some(bg) = style.bg.and_then(color_to_css) {
+        let _ = write!(css, "background-color: {}; ", bg);
+    }
+    if style.add_modifier.contains(Modifier::BOLD) {
+        css.push_str("font-weight: bold; ");
+    }
+    if style.add_modifier.contains(Modifier::ITALIC) {
+        css.push_str("font-style: italic; ");
+    }
+    if style.add_modifier.contains(Modifier::UNDERLINED) {
+        css.push_str("text-decoration: underline; ");
+    }
+    if style.add_modifier.contains(Modifier::CROSSED_OUT) {
+        css.push_str("text-decoration: line-through; ");
+    }
+
+    css
+}
+
+/// Converts a terminal colour to CSS, using the xterm defaults for the named colours
+fn color_to_css(color: Color) -> Option<String> {
+    let (r, g, b) = match color {
+        Color::Reset => return None,
+        Color::Black => (0, 0, 0),
+        Color::Red => (205, 0, 0),
+        Color::Green => (0, 205, 0),
+        Color::Yellow => (205, 205, 0),
+        Color::Blue => (0, 0, 238),
+        Color::Magenta => (205, 0, 205),
+        Color::Cyan => (0, 205, 205),
+        Color::Gray => (229, 229, 229),
+        Color::DarkGray => (127, 127, 127),
+        Color::LightRed => (255, 0, 0),
+        Color::LightGreen => (0, 255, 0),
+        Color::LightYellow => (255, 255, 0),
+        Color::LightBlue => (92, 92, 255),
+        Color::LightMagenta => (255, 0, 255),
+        Color::LightCyan => (0, 255, 255),
+        Color::White => (255, 255, 255),
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(index) => indexed_to_rgb(index),
+    };
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+/// Converts an xterm 256 colour index to RGB
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    const BASIC: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+
+    match index {
+        0..=15 => BASIC[index as usize],
+        16..=231 => {
+            let index = index - 16;
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            (level(index / 36), level((index / 6) % 6), level(index % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_render_table_highlights() {
+        let diff = Diff::parse_diff("@@ -1 +1 @@\n-let a = 1;\n+let a = 2;\n");
+        let html = render_table(&[
+            ("Original".to_string(), diff.old_diff()),
+            ("New".to_string(), diff.current_diff()),
+        ]);
+
+        assert!(html.contains("<span class=\"removal-highlight\">1</span>"));
+        assert!(html.contains("<span class=\"addition-highlight\">2</span>"));
+    }
+}
@@ -0,0 +1,348 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::ValueEnum;
+use crossterm::{cursor::MoveTo, QueueableCommand};
+use image::{imageops::FilterType, RgbaImage};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Color,
+    text::Span,
+    widgets::{Block, BorderType, Paragraph, Widget},
+};
+use serde::{Deserialize, Serialize};
+use std::io::{stdout, Write};
+
+use crate::{
+    app::App,
+    services::image::{ImageDiff, ImageSide},
+    widget::Theme,
+};
+
+/// Bordered line above the image panes with the dimension and size changes
+const SUMMARY_HEIGHT: u16 = 3;
+
+/// Tallest image pane drawn by `--print`, in rows
+const PRINT_MAX_ROWS: u16 = 40;
+
+/// Assumed size in pixels of a terminal cell, when the terminal doesn't report it
+const DEFAULT_CELL_SIZE: (u16, u16) = (10, 20);
+
+/// Kitty payloads are sent in chunks of at most this many base64 bytes
+const KITTY_CHUNK_LEN: usize = 4096;
+
+/// How images are drawn in the terminal
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageProtocol {
+    /// Pick from the terminal's environment, falling back to half blocks
+    #[default]
+    Auto,
+    /// The kitty graphics protocol (kitty, WezTerm, Ghostty)
+    Kitty,
+    /// Sixel graphics (foot, mlterm, iTerm2, xterm with sixel enabled)
+    Sixel,
+    /// Coloured `▀` characters, works in any truecolour terminal
+    Halfblocks,
+}
+
+impl ImageProtocol {
+    /// Resolves `Auto` from the terminal's environment variables
+    pub fn resolve(self) -> Self {
+        if self != ImageProtocol::Auto {
+            return self;
+        }
+
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        let term_program = var("TERM_PROGRAM");
+
+        // Escape sequences would need wrapping to get through tmux or screen
+        if std::env::var_os("TMUX").is_some() || term.starts_with("screen") {
+            return ImageProtocol::Halfblocks;
+        }
+        if std::env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || matches!(term_program.as_str(), "WezTerm" | "ghostty")
+        {
+            return ImageProtocol::Kitty;
+        }
+        if term.contains("foot")
+            || term.contains("mlterm")
+            || term.contains("sixel")
+            || term_program == "iTerm.app"
+        {
+            return ImageProtocol::Sixel;
+        }
+        ImageProtocol::Halfblocks
+    }
+}
+
+/// Draws the summary and the old and new images side by side, as half blocks
+pub fn render_image_diff(title: &str, images: &ImageDiff, area: Rect, buf: &mut Buffer) {
+    // TODO: The theme should be a property of the model
+    let theme = Theme::default();
+    let (summary_area, panes) = layout(area);
+
+    Paragraph::new(images.summary())
+        .block(
+            Block::bordered()
+                .title(Span::styled(title, theme.title))
+                .style(theme.border)
+                .border_type(BorderType::Plain),
+        )
+        .render(summary_area, buf);
+
+    let sides = [("Original", images.old_side()), ("New", images.new_side())];
+    for ((side, image), pane) in sides.into_iter().zip(panes) {
+        let title = match image {
+            Some(image) => {
+                let (width, height) = image.dimensions();
+                format!("{} ({}×{})", side, width, height)
+            }
+            None => side.to_string(),
+        };
+        let block = Block::bordered()
+            .title(Span::styled(title, theme.title))
+            .style(theme.border)
+            .border_type(BorderType::Plain);
+        let inner = block.inner(pane);
+        block.render(pane, buf);
+
+        match image {
+            Some(image) => render_halfblocks(image.image(), inner, buf),
+            None => Paragraph::new("No image").render(inner, buf),
+        }
+    }
+}
+
+/// Rows `render_image_diff` needs at `width` columns to show both images whole
+pub fn preview_height(images: &ImageDiff, width: u16) -> u16 {
+    // Only the pane width matters here, `Rect::new` clamps large areas
+    let (_, [pane, _]) = layout(Rect::new(0, 0, width, SUMMARY_HEIGHT + 3));
+    let inner = Block::bordered().inner(pane);
+
+    let rows = [images.old_side(), images.new_side()]
+        .into_iter()
+        .flatten()
+        .map(|side| fit(side.dimensions(), inner.width, PRINT_MAX_ROWS).1)
+        .max()
+        .unwrap_or(1);
+    SUMMARY_HEIGHT + rows.max(1) + 2
+}
+
+/// Summary line above two equal panes
+fn layout(area: Rect) -> (Rect, [Rect; 2]) {
+    let [summary, panes] =
+        Layout::vertical([Constraint::Length(SUMMARY_HEIGHT), Constraint::Min(0)]).areas(area);
+    let panes = Layout::horizontal(Constraint::from_percentages([50, 50])).areas(panes);
+    (summary, panes)
+}
+
+/// Largest size in cells, `(columns, rows)`, that keeps the image's aspect ratio within
+/// `columns` by `rows`. A cell is taken to be twice as tall as it is wide.
+fn fit((width, height): (u32, u32), columns: u16, rows: u16) -> (u16, u16) {
+    if width == 0 || height == 0 || columns == 0 || rows == 0 {
+        return (0, 0);
+    }
+    let scale = f64::min(
+        f64::from(columns) / f64::from(width),
+        f64::from(rows) * 2.0 / f64::from(height),
+    );
+    let fitted_columns = (f64::from(width) * scale)
+        .round()
+        .clamp(1.0, columns.into());
+    let fitted_rows = (f64::from(height) * scale / 2.0)
+        .ceil()
+        .clamp(1.0, rows.into());
+    (fitted_columns as u16, fitted_rows as u16)
+}
+
+/// Draws an image with two pixels per cell, the top one as the `▀` foreground and the bottom
+/// one as the background
+fn render_halfblocks(image: &RgbaImage, area: Rect, buf: &mut Buffer) {
+    let (columns, rows) = fit(image.dimensions(), area.width, area.height);
+    if columns == 0 {
+        return;
+    }
+    let scaled = image::imageops::resize(
+        image,
+        columns.into(),
+        u32::from(rows) * 2,
+        FilterType::Triangle,
+    );
+
+    let color = |x: u32, y: u32| match scaled.get_pixel_checked(x, y) {
+        Some(pixel) if pixel[3] >= 128 => Color::Rgb(pixel[0], pixel[1], pixel[2]),
+        _ => Color::Reset,
+    };
+
+    for row in 0..rows {
+        for column in 0..columns {
+            let (x, y) = (u32::from(column), u32::from(row) * 2);
+            buf.get_mut(area.x + column, area.y + row)
+                .set_symbol("▀")
+                .set_fg(color(x, y))
+                .set_bg(color(x, y + 1));
+        }
+    }
+}
+
+/// Draws previews with the kitty or sixel protocol over the half blocks, after each frame.
+/// Images are written straight to the terminal, outside of ratatui's buffer.
+#[derive(Debug)]
+pub struct GraphicsLayer {
+    protocol: ImageProtocol,
+    /// File and pane areas of the images on screen
+    shown: Option<(String, [Rect; 2])>,
+}
+
+impl GraphicsLayer {
+    pub fn new(protocol: ImageProtocol) -> Self {
+        Self {
+            protocol: protocol.resolve(),
+            shown: None,
+        }
+    }
+
+    /// Draws the current file's images if they changed since the last frame. Returns `true`
+    /// when the terminal has to be cleared and redrawn first, to remove a previous sixel image.
+    pub fn draw(&mut self, app: &App, size: Rect) -> Result<bool> {
+        if matches!(
+            self.protocol,
+            ImageProtocol::Halfblocks | ImageProtocol::Auto
+        ) {
+            return Ok(false);
+        }
+
+        let preview = app
+            .diff()
+            .and_then(|diff| Some((diff.path()?, app.image_preview(diff)?)))
+            .filter(|_| app.conflict().is_none() && !app.overlay_open());
+        let placement = preview.map(|(path, _)| {
+            let (_, diff_area) = super::tree::split(app, super::layout(size)[1]);
+            let (_, panes) = layout(diff_area);
+            (
+                path.to_string(),
+                panes.map(|pane| Block::bordered().inner(pane)),
+            )
+        });
+        if placement == self.shown {
+            return Ok(false);
+        }
+
+        let mut out = stdout().lock();
+        if self.shown.is_some() {
+            match self.protocol {
+                ImageProtocol::Kitty => write!(out, "\x1b_Ga=d,d=A,q=2\x1b\\")?,
+                _ => {
+                    self.shown = None;
+                    return Ok(true);
+                }
+            }
+        }
+
+        if let (Some((_, images)), Some((_, areas))) = (preview, &placement) {
+            let sides = [images.old_side(), images.new_side()];
+            for (side, area) in sides.into_iter().zip(areas) {
+                let Some(side) = side else {
+                    continue;
+                };
+                let escape = match self.protocol {
+                    ImageProtocol::Kitty => kitty_escape(side, *area),
+                    _ => sixel_escape(side, *area),
+                };
+                match escape {
+                    Some(escape) => {
+                        out.queue(MoveTo(area.x, area.y))?;
+                        out.write_all(escape.as_bytes())?;
+                    }
+                    None => tracing::warn!("Couldn't encode image for the terminal"),
+                }
+            }
+        }
+        out.flush()?;
+
+        self.shown = placement;
+        Ok(false)
+    }
+}
+
+/// Image scaled to the pixels behind its fitted cells, with the fitted `(columns, rows)`
+fn scale_to_cells(side: &ImageSide, area: Rect) -> Option<(RgbaImage, u16, u16)> {
+    let (columns, rows) = fit(side.dimensions(), area.width, area.height);
+    if columns == 0 {
+        return None;
+    }
+
+    let (cell_width, cell_height) = crossterm::terminal::window_size()
+        .ok()
+        .filter(|size| size.width > 0 && size.columns > 0 && size.rows > 0)
+        .map(|size| (size.width / size.columns, size.height / size.rows))
+        .unwrap_or(DEFAULT_CELL_SIZE);
+
+    let image = image::imageops::resize(
+        side.image(),
+        u32::from(columns) * u32::from(cell_width),
+        u32::from(rows) * u32::from(cell_height),
+        FilterType::Triangle,
+    );
+    Some((image, columns, rows))
+}
+
+/// Transmits and displays raw RGBA pixels, scaled by kitty into the fitted cells
+fn kitty_escape(side: &ImageSide, area: Rect) -> Option<String> {
+    let (image, columns, rows) = scale_to_cells(side, area)?;
+    let payload = STANDARD.encode(image.as_raw());
+
+    let mut escape = String::new();
+    let chunks = payload.as_bytes().chunks(KITTY_CHUNK_LEN);
+    let last = chunks.len().saturating_sub(1);
+    for (index, chunk) in chunks.enumerate() {
+        let more = u8::from(index != last);
+        let chunk = std::str::from_utf8(chunk).ok()?;
+        if index == 0 {
+            escape.push_str(&format!(
+                "\x1b_Ga=T,f=32,s={},v={},c={},r={},C=1,q=2,m={};{}\x1b\\",
+                image.width(),
+                image.height(),
+                columns,
+                rows,
+                more,
+                chunk
+            ));
+        } else {
+            escape.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk));
+        }
+    }
+    Some(escape)
+}
+
+fn sixel_escape(side: &ImageSide, area: Rect) -> Option<String> {
+    let (image, ..) = scale_to_cells(side, area)?;
+    icy_sixel::sixel_string(
+        image.as_raw(),
+        image.width() as i32,
+        image.height() as i32,
+        icy_sixel::PixelFormat::RGBA8888,
+        icy_sixel::DiffusionMethod::Auto,
+        icy_sixel::MethodForLargest::Auto,
+        icy_sixel::MethodForRep::Auto,
+        icy_sixel::Quality::AUTO,
+    )
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_keeps_aspect_ratio() {
+        // Cells are twice as tall as wide, so a square image takes half as many rows
+        assert_eq!(fit((100, 100), 40, 40), (40, 20));
+        assert_eq!(fit((100, 50), 40, 5), (20, 5));
+        assert_eq!(fit((0, 10), 40, 40), (0, 0));
+    }
+}
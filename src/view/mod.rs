@@ -1,6 +1,12 @@
 pub mod body;
 pub mod footer;
 pub mod header;
+pub mod help;
+pub mod html;
+pub mod image;
+pub mod palette;
+pub mod print;
+pub mod tree;
 
 use ratatui::{
     layout::{Constraint, Layout, Rect},
@@ -9,7 +15,10 @@ use ratatui::{
 
 use crate::app::App;
 
-use self::{body::render_body, footer::render_footer, header::render_header};
+use self::{
+    body::render_body, footer::render_footer, header::render_header, help::render_help,
+    palette::render_palette,
+};
 
 /// Renders all the components
 pub fn view(model: &mut App, f: &mut Frame) {
@@ -17,18 +26,30 @@ pub fn view(model: &mut App, f: &mut Frame) {
     let size = f.size();
     check_size(&size);
 
-    let [header, body, footer] = Layout::vertical([
-        Constraint::Length(3),
-        Constraint::Min(10),
-        Constraint::Max(10),
-    ])
-    .areas(size);
+    let [header, body, footer] = layout(size);
 
-    render_header(f, header);
+    render_header(model, f, header);
 
     render_body(model, f, body);
 
     render_footer(model, footer, f);
+
+    if model.show_help() {
+        render_help(model, f);
+    }
+    if let Some(palette) = model.palette() {
+        render_palette(model, palette, f);
+    }
+}
+
+/// Splits the terminal into the header, body and footer
+fn layout(size: Rect) -> [Rect; 3] {
+    Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(10),
+        Constraint::Max(10),
+    ])
+    .areas(size)
 }
 
 /// Checks terminal size is large enough
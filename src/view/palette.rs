@@ -0,0 +1,67 @@
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, List, ListState, Paragraph},
+    Frame,
+};
+
+use super::help::bound_keys;
+use crate::{
+    app::{
+        palette::{Palette, PaletteItem},
+        App,
+    },
+    widget::Theme,
+};
+
+/// Draws the command palette over the middle of the screen: the query, then the matching
+/// actions with their keys and the matching files
+pub(super) fn render_palette(app: &App, palette: &Palette, f: &mut Frame) {
+    // TODO: The theme should be a property of the model
+    let theme = Theme::default();
+    let area = centered(f.size(), palette.matches().len());
+    f.render_widget(Clear, area);
+
+    let block = Block::bordered()
+        .title(Span::styled("Commands", theme.title))
+        .border_type(BorderType::Plain)
+        .style(theme.title);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let [input, list] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+    f.render_widget(Paragraph::new(format!("> {}█", palette.query())), input);
+
+    let key_style = Style::default().fg(Color::LightCyan);
+    let file_style = Style::default().fg(Color::Gray);
+    let items = palette.matches().into_iter().map(|item| match item {
+        PaletteItem::Action(message) => Line::from(vec![
+            Span::raw(message.to_string()),
+            Span::styled(
+                format!("  {}", bound_keys(app.config().keymap(), *message)),
+                key_style,
+            ),
+        ]),
+        PaletteItem::File { path, .. } => Line::styled(format!("file: {}", path), file_style),
+    });
+
+    let mut state = ListState::default().with_selected(Some(palette.selected()));
+    f.render_stateful_widget(
+        List::new(items).highlight_style(theme.selected),
+        list,
+        &mut state,
+    );
+}
+
+/// A box in the middle of `area`, tall enough for `rows` matches where it fits
+fn centered(area: Rect, rows: usize) -> Rect {
+    let width = (area.width * 3 / 5).max(40).min(area.width);
+    let height = (rows as u16 + 3).clamp(5, area.height * 3 / 5);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + area.height / 5,
+        width,
+        height,
+    }
+}
@@ -0,0 +1,236 @@
+use crossterm::{
+    style::{Attribute, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    Command,
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{StatefulWidget, Widget},
+};
+use unicode_width::UnicodeWidthStr;
+
+use super::{
+    body::render_conflict,
+    image::{preview_height, render_image_diff},
+};
+use crate::{
+    app::App,
+    widget::{PaneOptions, SideBySideDiff, SideBySideDiffState, Theme},
+};
+
+/// Renders every file in the app once, top to bottom, for writing to stdout instead of the
+/// alternate screen. Uses the same panes as the interactive body, without a selected row.
+pub fn render_to_string(app: &App, width: u16, color: bool) -> String {
+    let mut output = String::new();
+
+    if let Some(conflict) = app.conflict() {
+        let options = PaneOptions {
+            theme: unstyled_selection(),
+            highlight_symbol: String::new(),
+            ..Default::default()
+        };
+        let render = |area, buf: &mut Buffer, offset| {
+            render_conflict(conflict, area, buf, &mut slice_state(offset), &options);
+        };
+        output.push_str(&render_sliced(
+            width,
+            conflict.rows().len(),
+            0,
+            color,
+            render,
+        ));
+    }
+
+    for diff in app.diffs() {
+        if let Some(images) = app.image_preview(diff) {
+            let area = Rect::new(0, 0, width, preview_height(images, width));
+            let mut buf = Buffer::empty(area);
+            render_image_diff(diff.path().unwrap_or_default(), images, area, &mut buf);
+            output.push_str(&buffer_to_string(&buf, color));
+            continue;
+        }
+
+        let widget = SideBySideDiff::new(diff);
+        if !widget.has_rows() {
+            let area = Rect::new(0, 0, width, widget.height());
+            let mut buf = Buffer::empty(area);
+            Widget::render(widget, area, &mut buf);
+            output.push_str(&buffer_to_string(&buf, color));
+            continue;
+        }
+
+        let render = |area, buf: &mut Buffer, offset| {
+            let widget = SideBySideDiff::new(diff)
+                .theme(unstyled_selection())
+                .highlight_symbol("");
+            StatefulWidget::render(widget, area, buf, &mut slice_state(offset));
+        };
+        output.push_str(&render_sliced(
+            width,
+            diff.longest_diff_len() + 1,
+            widget.summary_height(),
+            color,
+            render,
+        ));
+    }
+
+    output
+}
+
+/// Renders bordered panes of `rows` rows, below `header` rows of anything else, in slices that
+/// each fit a buffer of at most `u16::MAX` cells. Each slice is drawn from its first row, and
+/// the header and borders where slices meet are dropped so they join up as one.
+fn render_sliced(
+    width: u16,
+    rows: usize,
+    header: usize,
+    color: bool,
+    render: impl Fn(Rect, &mut Buffer, usize),
+) -> String {
+    let chrome = header + 2;
+    let max_height = usize::from(u16::MAX / width.max(1));
+    let slice_rows = max_height.saturating_sub(chrome).max(1);
+
+    let mut output = String::new();
+    let mut offset = 0;
+    loop {
+        let rows_in_slice = rows.saturating_sub(offset).min(slice_rows);
+        let height = u16::try_from(rows_in_slice + chrome).unwrap_or(u16::MAX);
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        render(area, &mut buf, offset);
+
+        let text = buffer_to_string(&buf, color);
+        let lines: Vec<&str> = text.lines().collect();
+        let first = offset == 0;
+        let last = offset + rows_in_slice >= rows;
+        let start = if first { 0 } else { chrome - 1 };
+        let end = if last { lines.len() } else { lines.len() - 1 };
+        for line in &lines[start..end] {
+            output.push_str(line);
+            output.push('\n');
+        }
+
+        offset += rows_in_slice;
+        if last {
+            return output;
+        }
+    }
+}
+
+/// State drawing the panes from row `offset`. Tables scroll back to their selected row, taking
+/// the first row when there's none, so the slice's first row is selected instead.
+fn slice_state(offset: usize) -> SideBySideDiffState {
+    SideBySideDiffState::default()
+        .with_selected(Some(offset))
+        .with_offset(offset)
+}
+
+/// The default theme with the selected row drawn like the others, for [`slice_state`]
+fn unstyled_selection() -> Theme {
+    Theme {
+        selected: Style::default(),
+        ..Default::default()
+    }
+}
+
+/// Converts a rendered buffer to text, with ANSI escape codes for the cell styles when `color`
+/// is set
+fn buffer_to_string(buf: &Buffer, color: bool) -> String {
+    let area = buf.area;
+    let mut output = String::new();
+
+    for y in area.top()..area.bottom() {
+        let mut line = String::new();
+        let mut current_style = (Color::Reset, Color::Reset, Modifier::empty());
+        let mut to_skip = 0;
+
+        for x in area.left()..area.right() {
+            let cell = buf.get(x, y);
+
+            // Cells covered by a preceding wide character
+            if to_skip > 0 {
+                to_skip -= 1;
+                continue;
+            }
+            to_skip = cell.symbol().width().saturating_sub(1);
+
+            let style = (cell.fg, cell.bg, cell.modifier);
+            if color && style != current_style {
+                write_style(&mut line, style);
+                current_style = style;
+            }
+
+            line.push_str(cell.symbol());
+        }
+
+        if color {
+            write_command(&mut line, SetAttribute(Attribute::Reset));
+        } else {
+            line.truncate(line.trim_end().len());
+        }
+
+        output.push_str(&line);
+        output.push('\n');
+    }
+
+    output
+}
+
+fn write_style(line: &mut String, (fg, bg, modifier): (Color, Color, Modifier)) {
+    write_command(line, SetAttribute(Attribute::Reset));
+
+    if fg != Color::Reset {
+        write_command(line, SetForegroundColor(fg.into()));
+    }
+    if bg != Color::Reset {
+        write_command(line, SetBackgroundColor(bg.into()));
+    }
+
+    let attributes = [
+        (Modifier::BOLD, Attribute::Bold),
+        (Modifier::DIM, Attribute::Dim),
+        (Modifier::ITALIC, Attribute::Italic),
+        (Modifier::UNDERLINED, Attribute::Underlined),
+        (Modifier::REVERSED, Attribute::Reverse),
+        (Modifier::CROSSED_OUT, Attribute::CrossedOut),
+    ];
+    for (flag, attribute) in attributes {
+        if modifier.contains(flag) {
+            write_command(line, SetAttribute(attribute));
+        }
+    }
+}
+
+fn write_command(line: &mut String, command: impl Command) {
+    // Writing to a String can't fail
+    let _ = command.write_ansi(line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff::Diff, services::config::AppConfig};
+
+    #[test]
+    fn test_long_diff_printed_in_full() {
+        let lines: String = (1..=3000).map(|n| format!("{}\n", n)).collect();
+        let mut app = App::new(Default::default(), AppConfig::defaults().unwrap());
+        app.set_diffs(vec![Diff::from_texts(
+            &lines,
+            &lines.replace("\n5\n", "\nfive\n"),
+        )]);
+
+        // Far more cells than fit in one buffer
+        let output = render_to_string(&app, 120, false);
+        let output: Vec<&str> = output.lines().collect();
+
+        assert_eq!(output.len(), 3000 + 2);
+        assert!(output[0].starts_with('┌'));
+        assert!(output[5].contains("five"));
+        assert!(output[3000].contains("3000"));
+        assert!(output[3001].starts_with('└'));
+        assert_eq!(output.iter().filter(|line| line.contains('┌')).count(), 1);
+    }
+}
@@ -0,0 +1,100 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, List, ListState, StatefulWidget},
+};
+
+use crate::{
+    app::{
+        state::Focus,
+        tree::{TreeEntry, TreeEntryKind},
+        App,
+    },
+    diff::FileStatus,
+    widget::Theme,
+};
+
+/// Widest the sidebar gets, in columns
+const TREE_MAX_WIDTH: u16 = 40;
+
+/// Splits the body into the file tree sidebar, when shown, and the area left for the diff
+pub fn split(model: &App, area: Rect) -> (Option<Rect>, Rect) {
+    if !model.show_tree() || model.conflict().is_some() {
+        return (None, area);
+    }
+    let width = (area.width / 4).clamp(20, TREE_MAX_WIDTH);
+    let [tree, diff] =
+        Layout::horizontal([Constraint::Length(width), Constraint::Min(0)]).areas(area);
+    (Some(tree), diff)
+}
+
+/// Draws the changed files by directory, with their status and line counts
+pub(super) fn render_tree(model: &App, area: Rect, buf: &mut Buffer) {
+    // TODO: The theme should be a property of the model
+    let theme = Theme::default();
+    let tree = model.tree();
+    let focused = model.focus() == Focus::Tree;
+
+    let items = tree.visible().into_iter().map(|entry| {
+        let selected_file = entry.kind() == &TreeEntryKind::File(model.selected_file());
+        entry_line(model, entry, selected_file, &theme)
+    });
+
+    let list = List::new(items)
+        .block(
+            Block::bordered()
+                .title(Span::styled("Files", theme.title))
+                .style(if focused { theme.title } else { theme.border })
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(if focused {
+            theme.selected
+        } else {
+            Style::default()
+        });
+
+    let mut state = ListState::default().with_selected(Some(tree.selected()));
+    StatefulWidget::render(list, area, buf, &mut state);
+}
+
+fn entry_line<'a>(model: &'a App, entry: &'a TreeEntry, open: bool, theme: &Theme) -> Line<'a> {
+    let indent = Span::raw("  ".repeat(entry.depth()));
+
+    let diff = match entry.kind() {
+        TreeEntryKind::Directory(path) => {
+            let marker = if model.tree().is_collapsed(path) {
+                "▸"
+            } else {
+                "▾"
+            };
+            return Line::from(vec![
+                indent,
+                Span::raw(format!("{} {}/", marker, entry.name())),
+            ]);
+        }
+        TreeEntryKind::File(index) => &model.diffs()[*index],
+    };
+
+    let status = diff.status();
+    let status_style = match status {
+        FileStatus::Added => theme.addition_prefix,
+        FileStatus::Deleted => theme.removal_prefix,
+        FileStatus::Renamed | FileStatus::Copied => theme.moved_prefix,
+        FileStatus::Modified => Style::default().fg(Color::Yellow),
+    };
+    let name_style = if open {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+
+    Line::from(vec![
+        indent,
+        Span::styled(format!("{} ", status.letter()), status_style),
+        Span::styled(entry.name(), name_style),
+        Span::styled(format!(" +{}", diff.additions()), theme.addition_prefix),
+        Span::styled(format!(" -{}", diff.removals()), theme.removal_prefix),
+    ])
+}
@@ -0,0 +1,482 @@
+//! Ratatui widgets for drawing a [`Diff`] in other terminal applications.
+//!
+//! [`SideBySideDiff`] renders the original and new sides of a diff next to each other, keeping
+//! both panes on the same row through a single [`SideBySideDiffState`].
+//!
+//! ```
+//! use diff_tool::diff::Diff;
+//! use diff_tool::widget::{SideBySideDiff, SideBySideDiffState, Theme};
+//! use ratatui::{buffer::Buffer, layout::Rect, widgets::StatefulWidget};
+//!
+//! let diff = Diff::from_texts("one\ntwo\n", "one\n2\n");
+//! let mut state = SideBySideDiffState::default().with_selected(Some(1));
+//!
+//! let area = Rect::new(0, 0, 40, 4);
+//! let mut buf = Buffer::empty(area);
+//! SideBySideDiff::new(&diff)
+//!     .theme(Theme::default())
+//!     .gutter(true)
+//!     .render(area, &mut buf, &mut state);
+//!
+//! assert_eq!(state.selected(), Some(1));
+//! ```
+
+mod theme;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Paragraph, Row, StatefulWidget, Table, TableState, Widget},
+};
+use std::{borrow::Cow, ops::Range};
+
+pub use theme::Theme;
+
+use crate::diff::{Diff, DiffLine};
+
+/// Draws both sides of a [`Diff`] as two bordered panes sharing one selected row
+#[derive(Debug, Clone)]
+pub struct SideBySideDiff<'a> {
+    diff: &'a Diff,
+    old_title: Option<String>,
+    new_title: Option<String>,
+    options: PaneOptions,
+}
+
+/// Selection and scroll position shared by both panes of a [`SideBySideDiff`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SideBySideDiffState {
+    offset: usize,
+    selected: Option<usize>,
+}
+
+/// How the panes of a diff are drawn
+#[derive(Debug, Clone)]
+pub(crate) struct PaneOptions {
+    pub(crate) theme: Theme,
+    /// Line number and prefix columns
+    pub(crate) gutter: bool,
+    /// Emphasis of the changed characters within a line
+    pub(crate) highlights: bool,
+    pub(crate) highlight_symbol: String,
+    /// Tabs, carriage returns and trailing spaces drawn as visible symbols
+    pub(crate) visible_whitespace: bool,
+}
+
+impl Default for PaneOptions {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            gutter: true,
+            highlights: true,
+            highlight_symbol: ">>".to_string(),
+            visible_whitespace: false,
+        }
+    }
+}
+
+impl<'a> SideBySideDiff<'a> {
+    pub fn new(diff: &'a Diff) -> Self {
+        Self {
+            diff,
+            old_title: None,
+            new_title: None,
+            options: PaneOptions::default(),
+        }
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.options.theme = theme;
+        self
+    }
+
+    /// Shows the line number and `+`/`-` prefix columns, on by default
+    pub fn gutter(mut self, gutter: bool) -> Self {
+        self.options.gutter = gutter;
+        self
+    }
+
+    /// Emphasises the characters that changed within paired lines, on by default
+    pub fn highlights(mut self, highlights: bool) -> Self {
+        self.options.highlights = highlights;
+        self
+    }
+
+    /// Marker drawn before the selected row, `>>` by default
+    pub fn highlight_symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.options.highlight_symbol = symbol.into();
+        self
+    }
+
+    /// Draws tabs as `→`, carriage returns as `␍` and trailing spaces as `·`, off by default
+    pub fn visible_whitespace(mut self, visible: bool) -> Self {
+        self.options.visible_whitespace = visible;
+        self
+    }
+
+    /// Overrides the `Original: <path>` title of the left pane
+    pub fn old_title(mut self, title: impl Into<String>) -> Self {
+        self.old_title = Some(title.into());
+        self
+    }
+
+    /// Overrides the `New: <path> (<file level changes>)` title of the right pane
+    pub fn new_title(mut self, title: impl Into<String>) -> Self {
+        self.new_title = Some(title.into());
+        self
+    }
+
+    /// Rows needed to draw the whole diff without scrolling, including borders
+    pub fn height(&self) -> u16 {
+        let rows = self.diff.longest_diff_len() + 1;
+        let panes = match self.diff.binary() {
+            Some(_) if !self.has_rows() => usize::from(BINARY_SUMMARY_HEIGHT),
+            Some(_) => rows + 2 + usize::from(BINARY_SUMMARY_HEIGHT),
+            None => rows + 2,
+        };
+        panes.min(u16::MAX.into()) as u16
+    }
+
+    /// Rows drawn above the panes, the summary line of a binary diff
+    pub(crate) fn summary_height(&self) -> usize {
+        match self.diff.binary() {
+            Some(_) => usize::from(BINARY_SUMMARY_HEIGHT),
+            None => 0,
+        }
+    }
+
+    pub(crate) fn has_rows(&self) -> bool {
+        !self.diff.old_diff().is_empty() || !self.diff.current_diff().is_empty()
+    }
+
+    /// Draws the summary line of a binary diff at the top of `area`, returning what's left for
+    /// its hex dump panes
+    fn render_binary_summary(&self, area: Rect, buf: &mut Buffer) -> Option<Rect> {
+        let Some(summary) = self.diff.binary() else {
+            return Some(area);
+        };
+        let theme = &self.options.theme;
+
+        let [summary_area, panes_area] = Layout::vertical([
+            Constraint::Length(BINARY_SUMMARY_HEIGHT),
+            Constraint::Min(0),
+        ])
+        .areas(area);
+
+        Paragraph::new(summary.to_string())
+            .block(
+                Block::bordered()
+                    .title(Span::styled(
+                        self.diff.path().unwrap_or("Binary"),
+                        theme.title,
+                    ))
+                    .style(theme.border)
+                    .border_type(BorderType::Plain),
+            )
+            .render(summary_area, buf);
+
+        self.has_rows().then_some(panes_area)
+    }
+}
+
+/// Bordered line above the panes of a binary diff
+const BINARY_SUMMARY_HEIGHT: u16 = 3;
+
+impl SideBySideDiffState {
+    pub fn with_selected(mut self, selected: Option<usize>) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn select(&mut self, selected: Option<usize>) {
+        self.selected = selected;
+        if selected.is_none() {
+            self.offset = 0;
+        }
+    }
+
+    /// Selects the row after the current one, wrapping to the first of `rows`
+    pub fn select_next(&mut self, rows: usize) {
+        let row = match self.selected {
+            Some(i) if i + 1 < rows => i + 1,
+            _ => 0,
+        };
+        self.select(Some(row));
+    }
+
+    /// Selects the row before the current one, wrapping to the last of `rows`
+    pub fn select_previous(&mut self, rows: usize) {
+        let row = match self.selected {
+            Some(0) | None => rows.saturating_sub(1),
+            Some(i) => i.min(rows) - 1,
+        };
+        self.select(Some(row));
+    }
+
+    pub fn select_first(&mut self) {
+        self.select(Some(0));
+    }
+
+    pub fn select_last(&mut self, rows: usize) {
+        self.select(Some(rows.saturating_sub(1)));
+    }
+
+    /// Table state for one pane; render with a copy and write the offset back
+    pub(crate) fn table_state(&self) -> TableState {
+        TableState::default()
+            .with_offset(self.offset)
+            .with_selected(self.selected)
+    }
+
+    pub(crate) fn set_offset(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+}
+
+impl StatefulWidget for SideBySideDiff<'_> {
+    type State = SideBySideDiffState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let Some(area) = self.render_binary_summary(area, buf) else {
+            return;
+        };
+        let [left_side, right_side] =
+            Layout::horizontal(Constraint::from_percentages([50, 50])).areas(area);
+
+        let diff = self.diff;
+        let line_number_width = diff.largest_line_number_char_len();
+        let old_title = self
+            .old_title
+            .unwrap_or_else(|| pane_title("Original", diff.old_path()));
+        let new_title = self.new_title.unwrap_or_else(|| {
+            let title = pane_title("New", diff.new_path());
+            match diff.change_description() {
+                Some(change) => format!("{} ({})", title, change),
+                None => title,
+            }
+        });
+
+        let old_table = pane_table(
+            diff.old_diff(),
+            old_title,
+            line_number_width,
+            false,
+            &self.options,
+        );
+        let new_table = pane_table(
+            diff.current_diff(),
+            new_title,
+            line_number_width,
+            true,
+            &self.options,
+        );
+
+        let mut table_state = state.table_state();
+        StatefulWidget::render(old_table, left_side, buf, &mut table_state.clone());
+        StatefulWidget::render(new_table, right_side, buf, &mut table_state);
+        state.set_offset(table_state.offset());
+    }
+}
+
+impl Widget for SideBySideDiff<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        StatefulWidget::render(self, area, buf, &mut SideBySideDiffState::default());
+    }
+}
+
+/// Labels a pane with the path of its side of the diff, when known
+fn pane_title(side: &str, path: Option<&str>) -> String {
+    match path {
+        Some(path) => format!("{}: {}", side, path),
+        None => side.to_string(),
+    }
+}
+
+/// Builds the table drawing one side of a diff. Only panes with `show_selected` set apply the
+/// theme's selected style, the others just carry the highlight symbol.
+pub(crate) fn pane_table<'a>(
+    lines: &'a [DiffLine],
+    title: String,
+    line_number_width: u16,
+    show_selected: bool,
+    options: &PaneOptions,
+) -> Table<'a> {
+    let theme = &options.theme;
+    let rows = lines.iter().map(|line| pane_row(line, options));
+
+    // Line Number col depends on the largest line number
+    let widths = if options.gutter {
+        vec![
+            Constraint::Length(line_number_width),
+            Constraint::Percentage(2),
+            Constraint::Percentage(97),
+        ]
+    } else {
+        vec![Constraint::Percentage(100)]
+    };
+
+    Table::new(rows, widths)
+        .block(
+            Block::bordered()
+                .title(Span::styled(title, theme.title))
+                .style(theme.border)
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(if show_selected {
+            theme.selected
+        } else {
+            Style::default()
+        })
+        .highlight_symbol(options.highlight_symbol.clone())
+}
+
+fn pane_row<'a>(line: &'a DiffLine, options: &PaneOptions) -> Row<'a> {
+    let theme = &options.theme;
+    let (prefix_style, content_style) = theme.line_styles(*line.kind());
+
+    let content = if options.highlights {
+        Line::from(highlighted_spans(line, content_style, options))
+    } else {
+        Line::raw(display_text(
+            line.content(),
+            0..line.content().len(),
+            options.visible_whitespace,
+        ))
+    }
+    .style(content_style);
+
+    if !options.gutter {
+        return Row::new([content]);
+    }
+
+    let line_number = match line.line_number() {
+        Some(x) => x.to_string(),
+        None => " ".to_string(),
+    };
+
+    Row::new([
+        Line::styled(line_number, theme.line_number).right_aligned(),
+        Line::styled(line.kind().value(), prefix_style).centered(),
+        content,
+    ])
+}
+
+/// Splits a line's content into spans, emphasising the characters that changed within it
+pub(crate) fn highlighted_spans<'a>(
+    line: &'a DiffLine,
+    content_style: Style,
+    options: &PaneOptions,
+) -> Vec<Span<'a>> {
+    let content = line.content();
+    let highlight_style = options.theme.highlight_style(*line.kind());
+    let text = |range: Range<usize>| display_text(content, range, options.visible_whitespace);
+
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for range in line.highlights() {
+        if range.start > offset {
+            spans.push(Span::styled(text(offset..range.start), content_style));
+        }
+        spans.push(Span::styled(text(range.clone()), highlight_style));
+        offset = range.end;
+    }
+    if offset < content.len() || spans.is_empty() {
+        spans.push(Span::styled(text(offset..content.len()), content_style));
+    }
+
+    spans
+}
+
+/// The part of a line's content in `range` as drawn. Carriage returns would move the cursor so
+/// are dropped, unless `visible` shows them and tabs and trailing spaces as symbols.
+fn display_text(content: &str, range: Range<usize>, visible: bool) -> Cow<'_, str> {
+    let text = &content[range.clone()];
+    if !visible {
+        if text.contains('\r') {
+            return Cow::Owned(text.replace('\r', ""));
+        }
+        return Cow::Borrowed(text);
+    }
+
+    let trailing = content.trim_end_matches([' ', '\t', '\r']).len();
+    text.char_indices()
+        .map(|(index, c)| match c {
+            '\t' => '→',
+            '\r' => '␍',
+            ' ' if range.start + index >= trailing => '·',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row_text(buf: &Buffer, y: u16) -> String {
+        (0..buf.area.width)
+            .map(|x| buf.get(x, y).symbol())
+            .collect()
+    }
+
+    #[test]
+    fn test_state_wraps_around() {
+        let mut state = SideBySideDiffState::default();
+        state.select_previous(3);
+        assert_eq!(state.selected(), Some(2));
+        state.select_next(3);
+        assert_eq!(state.selected(), Some(0));
+        state.select_last(3);
+        assert_eq!(state.selected(), Some(2));
+    }
+
+    #[test]
+    fn test_render_shares_selection() {
+        let diff = Diff::from_texts("one\ntwo\n", "one\n2\n");
+        let area = Rect::new(0, 0, 40, 4);
+        let mut buf = Buffer::empty(area);
+        let mut state = SideBySideDiffState::default().with_selected(Some(1));
+
+        StatefulWidget::render(SideBySideDiff::new(&diff), area, &mut buf, &mut state);
+
+        let row = row_text(&buf, 2);
+        assert_eq!(row.matches(">>").count(), 2);
+        assert!(row.contains("two") && row.contains('2'));
+    }
+
+    #[test]
+    fn test_render_without_gutter() {
+        let diff = Diff::from_texts("one\n", "two\n");
+        let area = Rect::new(0, 0, 40, 3);
+        let mut buf = Buffer::empty(area);
+
+        Widget::render(SideBySideDiff::new(&diff).gutter(false), area, &mut buf);
+
+        let row = row_text(&buf, 1);
+        assert!(row.starts_with("│one"));
+        assert!(!row.contains('+'));
+    }
+
+    #[test]
+    fn test_display_text() {
+        let content = "\tx = 1;  \r";
+        assert_eq!(display_text(content, 0..content.len(), false), "\tx = 1;  ");
+        assert_eq!(display_text(content, 0..content.len(), true), "→x = 1;··␍");
+        assert_eq!(display_text(content, 2..5, true), " = ");
+    }
+}
@@ -0,0 +1,93 @@
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::diff::DiffKind;
+
+/// Styles used to draw a diff, shared by the viewer and the exporters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub title: Style,
+    pub border: Style,
+    /// Style of the selected row
+    pub selected: Style,
+    pub line_number: Style,
+    pub addition_prefix: Style,
+    pub addition: Style,
+    /// Changed characters within an added line
+    pub addition_highlight: Style,
+    pub removal_prefix: Style,
+    pub removal: Style,
+    /// Changed characters within a removed line
+    pub removal_highlight: Style,
+    pub neutral: Style,
+    pub conflict_prefix: Style,
+    pub conflict: Style,
+    /// Lines of a block moved elsewhere in the file, on either side
+    pub moved_prefix: Style,
+    pub moved: Style,
+    /// Padding on the side of the diff without a matching line
+    pub blank: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            title: Style::default().fg(Color::LightCyan),
+            border: Style::default().fg(Color::White),
+            selected: Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+            line_number: Style::default().fg(Color::Gray),
+            addition_prefix: Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+            addition: Style::default()
+                .bg(Color::Rgb(131, 242, 140))
+                .fg(Color::Black),
+            addition_highlight: Style::default()
+                .bg(Color::Rgb(46, 160, 67))
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            removal_prefix: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            removal: Style::default().bg(Color::LightRed).fg(Color::Black),
+            removal_highlight: Style::default()
+                .bg(Color::Red)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            neutral: Style::default(),
+            conflict_prefix: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            conflict: Style::default().bg(Color::LightYellow).fg(Color::Black),
+            moved_prefix: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            moved: Style::default()
+                .bg(Color::Rgb(173, 216, 230))
+                .fg(Color::Black),
+            blank: Style::default().bg(Color::DarkGray),
+        }
+    }
+}
+
+impl Theme {
+    /// The prefix and content styles for a line of the given kind
+    pub fn line_styles(&self, kind: DiffKind) -> (Style, Style) {
+        match kind {
+            DiffKind::Addition => (self.addition_prefix, self.addition),
+            DiffKind::Removal => (self.removal_prefix, self.removal),
+            DiffKind::Neutral => (Style::default(), self.neutral),
+            DiffKind::Conflict => (self.conflict_prefix, self.conflict),
+            DiffKind::Moved => (self.moved_prefix, self.moved),
+            DiffKind::Blank => (Style::default(), self.blank),
+        }
+    }
+
+    /// The style for the changed characters within a line of the given kind
+    pub fn highlight_style(&self, kind: DiffKind) -> Style {
+        match kind {
+            DiffKind::Addition => self.addition_highlight,
+            DiffKind::Removal => self.removal_highlight,
+            _ => self.line_styles(kind).1,
+        }
+    }
+}